@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use notion_client::objects::{
+    block::{Block, BlockType},
+    rich_text::RichText,
+};
+use regex::{Captures, Regex};
+
+use crate::blocks::{self, compact_id, BlockNode};
+
+// notion.so/<ページ>#<ブロック> 形式のリンク（ワークスペース内の相対リンクも含む）
+const BLOCK_URL: &str = r"(?:https?://(?:www\.)?notion\.so)?/(?:[\w-]+/)?(?:[^\s()#?/]*-)?([0-9a-f]{32})(?:\?[^\s()#]*)?#([0-9a-f]{32})";
+
+// 別のブロックへのリンク（IDはハイフンなし）
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlockLink {
+    pub page_id: String,
+    pub block_id: String,
+}
+
+pub fn parse_block_link(url: &str) -> Option<BlockLink> {
+    let pattern = Regex::new(&format!("^{}$", BLOCK_URL)).unwrap();
+    let captures = pattern.captures(url)?;
+    Some(BlockLink {
+        page_id: captures[1].to_string(),
+        block_id: captures[2].to_string(),
+    })
+}
+
+pub fn anchor(block_id: &str) -> String {
+    format!("^{}", compact_id(block_id))
+}
+
+// 見出しは ^id を付けると見出しのテキストが変わるため、[[ページ#見出し]] でリンクする
+// Obsidianが見出しのリンクに使えない文字は空白にする
+pub fn heading_text(block: &Block) -> Option<String> {
+    if !matches!(
+        block.block_type,
+        BlockType::Heading1 { .. } | BlockType::Heading2 { .. } | BlockType::Heading3 { .. }
+    ) {
+        return None;
+    }
+    let text = blocks::plain_text(blocks::rich_text(block)?);
+    Some(
+        text.replace(['#', '|', '^', ':', '%', '[', ']'], " ")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+// ツリー内の見出しのブロックID（ハイフンなし）からリンクに使うテキストへの対応
+pub fn headings(tree: &[BlockNode]) -> HashMap<String, String> {
+    let mut headings = HashMap::new();
+    BlockNode::walk(tree, &mut |node| {
+        if let Some(text) = heading_text(&node.block) {
+            headings.insert(compact_id(node.id()), text);
+        }
+    });
+    headings
+}
+
+// ツリー内のブロックへのリンクを列挙する
+pub fn block_links(tree: &[BlockNode]) -> Vec<BlockLink> {
+    let mut links = Vec::new();
+    BlockNode::walk(tree, &mut |node| {
+        for rich_text in blocks::rich_text(&node.block).unwrap_or_default() {
+            if let RichText::Text {
+                href: Some(href), ..
+            } = rich_text
+            {
+                if let Some(link) = parse_block_link(href) {
+                    if !links.contains(&link) {
+                        links.push(link);
+                    }
+                }
+            }
+        }
+    });
+    links
+}
+
+// ブロックへのリンクを [[ページ#^id|テキスト]]（見出しは [[ページ#見出し|テキスト]]）に置き換える
+// titlesはページID（ハイフンなし）からノート名、headingsは見出しのブロックIDからテキストへの対応
+pub fn rewrite_links(
+    content: &str,
+    titles: &HashMap<String, String>,
+    headings: &HashMap<String, String>,
+) -> String {
+    let pattern = Regex::new(&format!(r"\[([^\]]*)\]\(({})\)", BLOCK_URL)).unwrap();
+    content
+        .split('\n')
+        .map(|line| {
+            // 表のセル内では列の区切りと解釈されないようにエスケープする
+            let separator = if line.trim_start().starts_with('|') {
+                "\\|"
+            } else {
+                "|"
+            };
+            pattern.replace_all(line, |captures: &Captures| {
+                let Some(title) = titles.get(&captures[3]) else {
+                    return captures[0].to_string();
+                };
+                let target = match headings.get(&captures[4]) {
+                    Some(heading) => format!("{}#{}", title, heading),
+                    None => format!("{}#{}", title, anchor(&captures[4])),
+                };
+                let label = &captures[1];
+                if label.is_empty() || label == &captures[2] {
+                    format!("[[{}]]", target)
+                } else {
+                    format!("[[{}{}{}]]", target, separator, label)
+                }
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,35 @@
+use serde_json::Value;
+
+use crate::error::NotionApiError;
+
+// notion_clientが対応していないAPI（プロパティのページ送りなど）を直接呼び出す
+// Notion-Versionはnotion_client（^1.0）が送るバージョンに合わせている。notion_clientを更新したら確認する
+const NOTION_API_URL: &str = "https://api.notion.com/v1";
+const NOTION_VERSION: &str = "2022-06-28";
+
+// pathは "/pages/<id>/properties/<id>" の形式。成功した場合はレスポンスの本文を返す
+// 通信のエラーはreqwestのエラー、エラーレスポンスはステータスとメッセージをNotionApiErrorにする
+pub(crate) async fn get(
+    token: &str,
+    path: &str,
+    query: &[(&str, &str)],
+) -> Result<String, NotionApiError> {
+    let response = reqwest::Client::new()
+        .get(format!("{}{}", NOTION_API_URL, path))
+        .bearer_auth(token)
+        .header("Notion-Version", NOTION_VERSION)
+        .query(query)
+        .send()
+        .await
+        .map_err(NotionApiError::new)?;
+    let status = response.status();
+    let body = response.text().await.map_err(NotionApiError::new)?;
+    if !status.is_success() {
+        let message = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|error| error["message"].as_str().map(str::to_string))
+            .unwrap_or(body);
+        return Err(NotionApiError::with_status(status.as_u16(), message));
+    }
+    Ok(body)
+}
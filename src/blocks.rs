@@ -0,0 +1,151 @@
+use futures::future::BoxFuture;
+use notion_client::{
+    endpoints::Client,
+    objects::{
+        block::{Block, BlockType},
+        parent::Parent,
+        rich_text::{Mention, RichText},
+        user::User,
+    },
+};
+
+use crate::error::{NotionToObsidianError, Result};
+
+#[derive(Debug, Clone)]
+pub struct BlockNode {
+    pub block: Block,
+    pub children: Vec<BlockNode>,
+}
+
+impl BlockNode {
+    pub fn id(&self) -> &str {
+        self.block.id.as_deref().unwrap_or_default()
+    }
+
+    // ツリーを深さ優先で列挙する
+    pub fn walk<'a>(nodes: &'a [BlockNode], visit: &mut impl FnMut(&'a BlockNode)) {
+        for node in nodes {
+            visit(node);
+            Self::walk(&node.children, visit);
+        }
+    }
+}
+
+pub fn rich_text(block: &Block) -> Option<&[RichText]> {
+    let rich_text = match &block.block_type {
+        BlockType::Paragraph { paragraph } => &paragraph.rich_text,
+        BlockType::Heading1 { heading_1 } => &heading_1.rich_text,
+        BlockType::Heading2 { heading_2 } => &heading_2.rich_text,
+        BlockType::Heading3 { heading_3 } => &heading_3.rich_text,
+        BlockType::BulletedListItem { bulleted_list_item } => &bulleted_list_item.rich_text,
+        BlockType::NumberedListItem { numbered_list_item } => &numbered_list_item.rich_text,
+        BlockType::ToDo { to_do } => &to_do.rich_text,
+        BlockType::Toggle { toggle } => &toggle.rich_text,
+        BlockType::Quote { quote } => &quote.rich_text,
+        BlockType::Callout { callout } => &callout.rich_text,
+        BlockType::Code { code } => &code.rich_text,
+        _ => return None,
+    };
+    Some(rich_text)
+}
+
+pub fn plain_text(rich_text: &[RichText]) -> String {
+    rich_text.iter().filter_map(|rt| rt.plain_text()).collect()
+}
+
+// notion_clientのUserはフィールドが非公開のため、シリアライズした値から読む
+pub fn user_id(user: &User) -> String {
+    user_field(user, "id").unwrap_or_default()
+}
+
+pub fn user_name(user: &User) -> Option<String> {
+    user_field(user, "name")
+}
+
+fn user_field(user: &User, key: &str) -> Option<String> {
+    serde_json::to_value(user)
+        .ok()?
+        .get(key)?
+        .as_str()
+        .map(str::to_string)
+}
+
+// ツリー内のメンションを表示テキストとともに列挙する
+pub fn mentions(tree: &[BlockNode]) -> Vec<(&str, &Mention)> {
+    let mut mentions = Vec::new();
+    BlockNode::walk(tree, &mut |node| {
+        for rich_text in rich_text(&node.block).unwrap_or_default() {
+            if let RichText::Mention {
+                mention,
+                plain_text,
+                ..
+            } = rich_text
+            {
+                mentions.push((plain_text.as_str(), mention));
+            }
+        }
+    });
+    mentions
+}
+
+// 子ブロックを再帰的に取得してツリーを構築する
+pub fn fetch_block_tree<'a>(
+    client: &'a Client,
+    block_id: &'a str,
+) -> BoxFuture<'a, Result<Vec<BlockNode>>> {
+    Box::pin(async move {
+        let mut nodes = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let response = client
+                .blocks
+                .retrieve_block_children(block_id, cursor.as_deref(), None)
+                .await
+                .map_err(|e| NotionToObsidianError::block_retrieval(block_id, e))?;
+
+            for block in response.results {
+                let children = match (&block.id, block.has_children) {
+                    (Some(id), Some(true)) => fetch_block_tree(client, id).await?,
+                    _ => Vec::new(),
+                };
+                nodes.push(BlockNode { block, children });
+            }
+
+            match response.next_cursor {
+                Some(next) if response.has_more => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        Ok(nodes)
+    })
+}
+
+// ブロックの親を辿って所属するページのIDを取得する
+pub async fn find_parent_page_id(client: &Client, block_id: &str) -> Result<String> {
+    let mut current = block_id.to_string();
+    loop {
+        let block = client
+            .blocks
+            .retrieve_a_block(&current)
+            .await
+            .map_err(|e| NotionToObsidianError::block_retrieval(&current, e))?;
+
+        match block.parent {
+            Some(Parent::PageId { page_id }) => return Ok(page_id),
+            Some(Parent::BlockId { block_id }) => current = block_id,
+            _ => {
+                return Err(NotionToObsidianError::ParentPageNotFound {
+                    block_id: block_id.to_string(),
+                })
+            }
+        }
+    }
+}
+
+// ハイフンを除いたID（Obsidianのブロック参照(^id)やファイル名に使う）
+// 先頭はタイムスタンプ由来で近い時期に作成したブロック同士が重なるため、切り詰めない
+pub fn compact_id(id: &str) -> String {
+    id.replace('-', "")
+}
@@ -1,11 +1,22 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
 use crate::{
+    columns::{ColumnLayout, ColumnListRenderer},
+    comments::CommentsMode,
+    converter::ConvertOptions,
+    dates::DateStyle,
+    export::NotionExport,
+    media::MediaOptions,
+    renderer::MarkdownRenderer,
+    synced::SyncedBlockMode,
     traits::{
+        block_renderer::BlockRenderer,
+        migration_observer::{MigrationObserver, NoopObserver},
         page_provider::{DatabasePageProvider, PageProvider},
         post_processor::{self, PostProcessor},
         DefaultFrontmatterGenerator, FrontmatterGenerator,
     },
+    users::UserDirectory,
     NotionToObsidian,
 };
 
@@ -15,6 +26,10 @@ pub struct NotionToObsidianBuilder {
     frontmatter_generator: Box<dyn FrontmatterGenerator>,
     post_processor: Box<dyn PostProcessor>,
     page_provider: Box<dyn PageProvider>,
+    observer: Box<dyn MigrationObserver>,
+    renderer: MarkdownRenderer,
+    options: ConvertOptions,
+    export: Option<Arc<NotionExport>>,
 }
 
 impl NotionToObsidianBuilder {
@@ -23,9 +38,13 @@ impl NotionToObsidianBuilder {
         NotionToObsidianBuilder {
             token,
             output_path: PathBuf::from("./"),
-            frontmatter_generator: Box::new(DefaultFrontmatterGenerator),
+            frontmatter_generator: Box::new(DefaultFrontmatterGenerator::default()),
             post_processor: Box::new(post_processor::DefaultPostProcessor),
             page_provider: Box::new(DatabasePageProvider::new("".to_string(), 100)),
+            observer: Box::new(NoopObserver),
+            renderer: MarkdownRenderer::new(),
+            options: ConvertOptions::default(),
+            export: None,
         }
     }
 
@@ -66,13 +85,128 @@ impl NotionToObsidianBuilder {
         }
     }
 
+    // 一括変換の進捗を受け取る
+    pub fn with_observer(self, observer: Box<dyn MigrationObserver>) -> NotionToObsidianBuilder {
+        NotionToObsidianBuilder { observer, ..self }
+    }
+
+    // 指定した種類のブロックの出力を差し替える（block_typeはNotion APIのtype名）
+    pub fn with_block_renderer(
+        mut self,
+        block_type: &str,
+        renderer: Box<dyn BlockRenderer>,
+    ) -> NotionToObsidianBuilder {
+        self.renderer.register(block_type, renderer);
+        self
+    }
+
+    // 段組み（column_list）の出力方法
+    pub fn with_column_layout(self, layout: ColumnLayout) -> NotionToObsidianBuilder {
+        self.with_block_renderer("column_list", Box::new(ColumnListRenderer::new(layout)))
+    }
+
+    pub fn with_synced_block_mode(
+        self,
+        synced_block_mode: SyncedBlockMode,
+    ) -> NotionToObsidianBuilder {
+        NotionToObsidianBuilder {
+            options: ConvertOptions {
+                synced_block_mode,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_comments_mode(self, comments_mode: CommentsMode) -> NotionToObsidianBuilder {
+        NotionToObsidianBuilder {
+            options: ConvertOptions {
+                comments_mode,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_user_directory(
+        self,
+        user_directory: Arc<UserDirectory>,
+    ) -> NotionToObsidianBuilder {
+        NotionToObsidianBuilder {
+            options: ConvertOptions {
+                user_directory: Some(user_directory),
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_date_style(self, date_style: DateStyle) -> NotionToObsidianBuilder {
+        NotionToObsidianBuilder {
+            options: ConvertOptions {
+                date_style: Some(date_style),
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_tasks_mode(self, tasks_mode: bool) -> NotionToObsidianBuilder {
+        NotionToObsidianBuilder {
+            options: ConvertOptions {
+                tasks_mode,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_media_options(self, media: MediaOptions) -> NotionToObsidianBuilder {
+        NotionToObsidianBuilder {
+            options: ConvertOptions {
+                media,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_block_anchors(self, block_anchors: bool) -> NotionToObsidianBuilder {
+        NotionToObsidianBuilder {
+            options: ConvertOptions {
+                block_anchors,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    // エクスポートのzipから変換する（トークン・ページの取得方法・後処理・ブロックの出力は使わない）
+    pub fn with_export(self, export: Arc<NotionExport>) -> NotionToObsidianBuilder {
+        NotionToObsidianBuilder {
+            export: Some(export),
+            ..self
+        }
+    }
+
     pub fn build(self) -> crate::error::Result<NotionToObsidian> {
-        NotionToObsidian::new(
-            self.token,
-            self.output_path,
-            self.frontmatter_generator,
-            self.post_processor,
-            self.page_provider,
-        )
+        let converter = match self.export {
+            Some(export) => NotionToObsidian::from_export(
+                export,
+                self.output_path,
+                self.frontmatter_generator,
+                self.options,
+            ),
+            None => NotionToObsidian::new(
+                self.token,
+                self.output_path,
+                self.frontmatter_generator,
+                self.post_processor,
+                self.page_provider,
+                self.renderer,
+                self.options,
+            ),
+        };
+        converter.map(|converter| converter.with_observer(self.observer))
     }
 }
@@ -0,0 +1,61 @@
+use crate::{
+    blocks::{compact_id, BlockNode},
+    renderer::prefix_lines,
+    traits::block_renderer::{BlockRenderer, RenderContext},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnLayout {
+    // 各列を順番に並べ、水平線で区切る
+    Sequential,
+    // Multi-Column Markdownプラグインの記法
+    MultiColumn,
+    // 列ごとに入れ子のコールアウトにする
+    Callouts,
+}
+
+// column_listブロックを指定のレイアウトで出力する
+pub struct ColumnListRenderer {
+    layout: ColumnLayout,
+}
+
+impl ColumnListRenderer {
+    pub fn new(layout: ColumnLayout) -> Self {
+        Self { layout }
+    }
+}
+
+impl BlockRenderer for ColumnListRenderer {
+    fn render(&self, node: &BlockNode, context: &RenderContext) -> String {
+        // 列内のブロックは元の順序のまま出力する
+        let columns: Vec<String> = node
+            .children
+            .iter()
+            .map(|column| context.render_children(column))
+            .filter(|column| !column.trim().is_empty())
+            .collect();
+
+        if columns.is_empty() {
+            return String::new();
+        }
+
+        match self.layout {
+            ColumnLayout::Sequential => columns.join("\n\n---\n\n"),
+            ColumnLayout::MultiColumn => format!(
+                "--- start-multi-column: ID_{}\n```column-settings\nNumber of Columns: {}\nLargest Column: standard\n```\n\n{}\n\n--- end-multi-column",
+                compact_id(node.id()),
+                columns.len(),
+                columns.join("\n\n--- column-break ---\n\n")
+            ),
+            ColumnLayout::Callouts => {
+                let columns: Vec<String> = columns
+                    .iter()
+                    .map(|column| {
+                        prefix_lines(&format!("> [!note]\n{}", prefix_lines(column, "> ")), ">")
+                    })
+                    .collect();
+                format!("> [!multi-column]\n>\n{}", columns.join("\n>\n"))
+            }
+        }
+    }
+}
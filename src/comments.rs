@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use notion_client::{endpoints::Client, objects::comment::Comment};
+
+use crate::{
+    blocks::{self, BlockNode},
+    error::{ErrorKind, NotionToObsidianError, Result},
+    i18n::Msg,
+    renderer::{block_kind, is_text_block},
+    tr,
+    traits::frontmatter_generator::format_datetime,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CommentsMode {
+    #[default]
+    Disabled,
+    // ページ末尾に ## Comments セクションを追加する
+    Section,
+    // 「<タイトル> Comments.md」として別ノートに書き出す
+    Sidecar,
+}
+
+#[derive(Debug, Clone)]
+pub struct CommentEntry {
+    pub discussion_id: String,
+    pub author: String,
+    pub created_time: DateTime<Utc>,
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub struct BlockComments {
+    pub block_id: String,
+    pub entries: Vec<CommentEntry>,
+}
+
+#[derive(Debug, Default)]
+pub struct PageComments {
+    pub page: Vec<CommentEntry>,
+    pub blocks: Vec<BlockComments>,
+}
+
+pub async fn fetch_comments(client: &Client, block_id: &str) -> Result<Vec<Comment>> {
+    let mut comments = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let response = client
+            .comments
+            // notion_client 1.0ではコメント一覧の取得がretrieve_a_userという名前になっている
+            .retrieve_a_user(block_id, cursor.as_deref(), None)
+            .await
+            .map_err(|e| NotionToObsidianError::comment_retrieval(block_id, e))?;
+
+        comments.extend(response.results);
+
+        match response.next_cursor {
+            Some(next) if response.has_more => cursor = Some(next),
+            _ => break,
+        }
+    }
+
+    Ok(comments)
+}
+
+// ページと各ブロックのコメントを取得する
+// 本文のないブロックのコメントは脚注を付けられないため、ページのコメントとして扱う
+pub async fn collect_page_comments(
+    client: &Client,
+    page_id: &str,
+    tree: &[BlockNode],
+    user_names: &mut HashMap<String, String>,
+) -> Result<PageComments> {
+    // コメントの読み取り権限がないインテグレーションでは、ブロックごとに取得しても失敗するだけなので省略する
+    let page_comments = match fetch_comments(client, page_id).await {
+        Ok(comments) => comments,
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+            warn!("{}", tr!(Msg::CommentsUnavailable, page_id, e));
+            return Ok(PageComments::default());
+        }
+        Err(e) => return Err(e),
+    };
+    let mut result = PageComments {
+        page: to_entries(client, page_comments, user_names).await,
+        ..Default::default()
+    };
+
+    let mut targets = Vec::new();
+    BlockNode::walk(tree, &mut |node| {
+        if has_comments(&block_kind(&node.block)) {
+            targets.push(node);
+        }
+    });
+
+    for node in targets {
+        let comments = fetch_comments(client, node.id()).await?;
+        if comments.is_empty() {
+            continue;
+        }
+        let entries = to_entries(client, comments, user_names).await;
+        if is_text_block(&block_kind(&node.block)) {
+            result.blocks.push(BlockComments {
+                block_id: node.id().to_string(),
+                entries,
+            });
+        } else {
+            result.page.extend(entries);
+        }
+    }
+
+    Ok(result)
+}
+
+// 列や同期ブロックなどの入れ物、区切り線などにはコメントを付けられないため取得しない
+fn has_comments(kind: &str) -> bool {
+    !matches!(
+        kind,
+        "column_list" | "column" | "synced_block" | "divider" | "table_of_contents" | "breadcrumb"
+    )
+}
+
+async fn to_entries(
+    client: &Client,
+    comments: Vec<Comment>,
+    user_names: &mut HashMap<String, String>,
+) -> Vec<CommentEntry> {
+    let mut entries = Vec::new();
+    for comment in comments {
+        let author =
+            resolve_user_name(client, &blocks::user_id(&comment.created_by), user_names).await;
+        entries.push(CommentEntry {
+            discussion_id: comment.discussion_id,
+            author,
+            created_time: comment.created_time,
+            text: blocks::plain_text(&comment.rich_text),
+        });
+    }
+    entries
+}
+
+// コメントの作成者はIDしか返ってこないため名前を引いてキャッシュする
+async fn resolve_user_name(
+    client: &Client,
+    user_id: &str,
+    user_names: &mut HashMap<String, String>,
+) -> String {
+    if let Some(name) = user_names.get(user_id) {
+        return name.clone();
+    }
+
+    let name = match client.users.retrieve_a_user(user_id).await {
+        Ok(user) => blocks::user_name(&user).unwrap_or_else(|| user_id.to_string()),
+        Err(_) => user_id.to_string(),
+    };
+    user_names.insert(user_id.to_string(), name.clone());
+    name
+}
+
+// 同じディスカッションのコメントは返信としてまとめる
+fn group_by_discussion(entries: &[CommentEntry]) -> Vec<Vec<&CommentEntry>> {
+    let mut groups: Vec<Vec<&CommentEntry>> = Vec::new();
+    for entry in entries {
+        match groups
+            .iter_mut()
+            .find(|group| group[0].discussion_id == entry.discussion_id)
+        {
+            Some(group) => group.push(entry),
+            None => groups.push(vec![entry]),
+        }
+    }
+    groups
+}
+
+fn format_entry(entry: &CommentEntry) -> String {
+    format!(
+        "**{}** ({}): {}",
+        entry.author,
+        format_datetime(entry.created_time),
+        entry.text.replace('\n', " ")
+    )
+}
+
+pub fn render_section(entries: &[CommentEntry]) -> String {
+    let mut section = String::from("## Comments\n\n");
+    for discussion in group_by_discussion(entries) {
+        section.push_str(&format!("- {}\n", format_entry(discussion[0])));
+        for reply in &discussion[1..] {
+            section.push_str(&format!("  - {}\n", format_entry(reply)));
+        }
+    }
+    section
+}
+
+pub fn render_footnote(label: &str, entries: &[CommentEntry]) -> String {
+    let lines: Vec<String> = entries.iter().map(format_entry).collect();
+    format!("[^{}]: {}", label, lines.join("\n    "))
+}
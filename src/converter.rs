@@ -1,27 +1,77 @@
-use log::info;
-use notion2md::builder::NotionToMarkdownBuilder;
+use log::{info, warn};
 use notion_client::{
-    endpoints::Client,
-    objects::{
-        block::Block,
-        file::File,
-        page::{Page, PageProperty},
-    },
+    endpoints::{databases::query::request::QueryDatabaseRequest, Client},
+    objects::page::{Page, PageProperty},
 };
 use regex::Regex;
-use std::{fs, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
 use crate::{
+    anchors::{self, BlockLink},
+    blocks::{compact_id, fetch_block_tree, find_parent_page_id, BlockNode},
+    comments::{self, CommentEntry, CommentsMode, PageComments},
+    dates::DateStyle,
     error::{NotionToObsidianError, Result},
-    traits::{page_provider::PageProvider, post_processor::PostProcessor, FrontmatterGenerator},
+    export::{self, NotionExport},
+    i18n::Msg,
+    markdown,
+    media::{self, MediaOptions},
+    migration::{
+        CancellationToken, Checkpoint, Journal, JournalEntry, JournalStage, MigrationReport,
+        PageOutcome, PageResult,
+    },
+    renderer::{MarkdownRenderer, RenderOptions},
+    synced::{self, SyncedBlockMode},
+    tasks::{self, TaskDatabase},
+    tr,
+    traits::{
+        page_provider::{ExportPageProvider, PageProvider},
+        post_processor::{DefaultPostProcessor, PostProcessor},
+        FrontmatterGenerator, MigrationEvent, MigrationObserver, NoopObserver,
+    },
+    users::{self, UserDirectory},
+    vault,
 };
 
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    pub synced_block_mode: SyncedBlockMode,
+    pub comments_mode: CommentsMode,
+    pub user_directory: Option<Arc<UserDirectory>>,
+    pub date_style: Option<DateStyle>,
+    pub tasks_mode: bool,
+    pub media: MediaOptions,
+    // 他のブロックから参照されているブロックに ^id を付け、リンクを書き換える
+    pub block_anchors: bool,
+}
+
 pub struct NotionToObsidian {
     client: Client,
     obsidian_dir: PathBuf,
     frontmatter_generator: Box<dyn FrontmatterGenerator>,
     post_processor: Box<dyn PostProcessor>,
     page_provider: Box<dyn PageProvider>,
+    observer: Box<dyn MigrationObserver>,
+    renderer: MarkdownRenderer,
+    options: ConvertOptions,
+    exported_synced_blocks: Mutex<HashSet<String>>,
+    // ブロックアンカー用（ブロックIDとページIDはハイフンなし）
+    referenced_blocks: Mutex<HashSet<String>>,
+    // 見出しのブロックIDからリンクに使うテキスト（Noneは取得して見出しでなかったブロック）
+    headings: Mutex<HashMap<String, Option<String>>>,
+    // 見出しを集めたページ（ハイフンなし）
+    scanned_pages: Mutex<HashSet<String>>,
+    page_titles: Mutex<HashMap<String, String>>,
+    // notion_idからバルト内の既存ノートへの対応（最初に保存するときに作成する）
+    notes: Mutex<Option<HashMap<String, PathBuf>>>,
+    journal: Mutex<Journal>,
+    // エクスポートのzipから変換する場合（APIは呼び出さない）
+    export: Option<Arc<NotionExport>>,
 }
 
 impl NotionToObsidian {
@@ -31,9 +81,58 @@ impl NotionToObsidian {
         frontmatter_generator: Box<dyn FrontmatterGenerator>,
         post_processor: Box<dyn PostProcessor>,
         page_provider: Box<dyn PageProvider>,
+        renderer: MarkdownRenderer,
+        options: ConvertOptions,
+    ) -> Result<Self> {
+        if token.is_empty() {
+            return Err(NotionToObsidianError::NoToken);
+        }
+        let client = Client::new(token, None).map_err(NotionToObsidianError::api)?;
+        Self::with_client(
+            client,
+            obsidian_dir,
+            frontmatter_generator,
+            post_processor,
+            page_provider,
+            renderer,
+            options,
+        )
+    }
+
+    // エクスポートのzipから変換する（Notionのページに反映できないため後処理は行わない）
+    pub fn from_export(
+        export: Arc<NotionExport>,
+        obsidian_dir: PathBuf,
+        frontmatter_generator: Box<dyn FrontmatterGenerator>,
+        options: ConvertOptions,
+    ) -> Result<Self> {
+        // FrontmatterGeneratorなどに渡すだけでリクエストは送らないため、トークンは不要
+        let client = Client::new(String::new(), None).map_err(NotionToObsidianError::api)?;
+        let converter = Self::with_client(
+            client,
+            obsidian_dir,
+            frontmatter_generator,
+            Box::new(DefaultPostProcessor),
+            Box::new(ExportPageProvider::new(export.clone())),
+            MarkdownRenderer::new(),
+            options,
+        )?;
+        Ok(Self {
+            export: Some(export),
+            ..converter
+        })
+    }
+
+    fn with_client(
+        client: Client,
+        obsidian_dir: PathBuf,
+        frontmatter_generator: Box<dyn FrontmatterGenerator>,
+        post_processor: Box<dyn PostProcessor>,
+        page_provider: Box<dyn PageProvider>,
+        renderer: MarkdownRenderer,
+        options: ConvertOptions,
     ) -> Result<Self> {
-        let client = Client::new(token, None)
-            .map_err(|e| NotionToObsidianError::ConversionError(e.to_string()))?;
+        let journal = Journal::load(&obsidian_dir)?;
 
         Ok(Self {
             client,
@@ -41,106 +140,795 @@ impl NotionToObsidian {
             frontmatter_generator,
             post_processor,
             page_provider,
+            observer: Box::new(NoopObserver),
+            renderer,
+            options,
+            exported_synced_blocks: Mutex::new(HashSet::new()),
+            referenced_blocks: Mutex::new(HashSet::new()),
+            headings: Mutex::new(HashMap::new()),
+            scanned_pages: Mutex::new(HashSet::new()),
+            page_titles: Mutex::new(HashMap::new()),
+            notes: Mutex::new(None),
+            journal: Mutex::new(journal),
+            export: None,
         })
     }
 
+    pub fn with_observer(self, observer: Box<dyn MigrationObserver>) -> Self {
+        Self { observer, ..self }
+    }
+
     pub async fn convert_page(&self, page_id: &str) -> Result<String> {
         let page = self
             .client
             .pages
             .retrieve_a_page(page_id, None)
             .await
-            .map_err(|e| NotionToObsidianError::PageRetrievalError(e.to_string()))?;
+            .map_err(|e| NotionToObsidianError::page_retrieval(page_id, e))?;
+
+        if self.options.media.page_assets {
+            if let Some(folder) = &self.options.media.attachments_folder {
+                let downloaded =
+                    media::download_page_assets(&page, &self.obsidian_dir, folder).await;
+                for (url, path) in downloaded {
+                    self.asset_downloaded(page_id, url, path);
+                }
+            }
+        }
+        let frontmatter = self.generate_frontmatter(&page, &self.client).await;
+
+        let tree = fetch_block_tree(&self.client, page_id).await?;
+        let mut count = 0;
+        BlockNode::walk(&tree, &mut |_| count += 1);
+        self.observer.on_event(&MigrationEvent::BlocksFetched {
+            page_id: page_id.to_string(),
+            count,
+        });
+
+        let media = media::collect(&tree, &self.obsidian_dir, &self.options.media).await;
+        if let Some(folder) = &self.options.media.attachments_folder {
+            for (url, filename) in &media.files {
+                let path = self.obsidian_dir.join(folder).join(filename);
+                self.asset_downloaded(page_id, url.clone(), path);
+            }
+        }
+        let mut render_options = RenderOptions {
+            media,
+            user_directory: self.options.user_directory.clone(),
+            date_style: self.options.date_style.clone(),
+            tasks_mode: self.options.tasks_mode,
+            ..Default::default()
+        };
+        self.prepare_synced_blocks(&tree, &mut render_options)
+            .await?;
 
-        let frontmatter = self.generate_frontmatter(&page, &self.client);
+        let page_comments = match self.options.comments_mode {
+            CommentsMode::Disabled => PageComments::default(),
+            _ => {
+                comments::collect_page_comments(&self.client, page_id, &tree, &mut HashMap::new())
+                    .await?
+            }
+        };
+        let mut footnotes = Vec::new();
+        for block in &page_comments.blocks {
+            let label = format!("c{}", footnotes.len() + 1);
+            footnotes.push(comments::render_footnote(&label, &block.entries));
+            render_options
+                .footnotes
+                .insert(compact_id(&block.block_id), label);
+        }
 
-        let converter = NotionToMarkdownBuilder::new(self.client.clone())
-            .build();
-        let content = converter.convert_page(page_id).await.map_err(|e| {
-            NotionToObsidianError::ConversionError(format!(
-                "Notionのページ {} の変換に失敗: {}",
-                page_id, e
-            ))
-        })?;
+        if self.options.block_anchors {
+            self.anchor_blocks(&tree, &mut render_options);
+        }
 
-        Ok(format!("{}{}", frontmatter, content))
+        let content = self.renderer.render_with(&tree, &render_options);
+        let content = if self.options.block_anchors {
+            self.link_blocks(page_id, &tree, content).await
+        } else {
+            content
+        };
+        let content = self.append_comments(&page, content, &page_comments.page, &footnotes)?;
+        if let Some(user_directory) = &self.options.user_directory {
+            self.write_user_stubs(user_directory, &page, &tree)?;
+        }
+
+        Ok(format!("{}{}", frontmatter, markdown::normalize(&content)))
     }
 
-    fn generate_frontmatter(&self, page: &Page, client: &Client) -> String {
+    // エクスポートのMarkdownにFrontmatterとCSVの列を付け、添付ファイルをバルトにコピーする
+    async fn convert_exported_page(&self, export: &NotionExport, page: &Page) -> Result<String> {
+        let exported = export.require_page(&page.id)?;
+        let frontmatter = self.generate_frontmatter(page, &self.client).await;
+        let frontmatter = exported.remove_unknown_keys(&frontmatter);
+        let frontmatter = export::insert_properties(&frontmatter, &exported.properties);
+
+        let folder = self.options.media.attachments_folder.as_deref();
+        let (content, attachments) = export.render(exported, folder);
+        for (source, target) in attachments {
+            let path = self.obsidian_dir.join(target);
+            export.copy_file(&source, &path)?;
+            self.asset_downloaded(&page.id, source, path);
+        }
+
+        Ok(format!("{}{}", frontmatter, markdown::normalize(&content)))
+    }
+
+    fn asset_downloaded(&self, page_id: &str, url: String, path: PathBuf) {
+        self.observer.on_event(&MigrationEvent::AssetDownloaded {
+            page_id: page_id.to_string(),
+            url,
+            path,
+        });
+    }
+
+    // 変換対象の全ページから参照されているブロックとページのタイトルを集める
+    // ツリーはページ数に比例してメモリを使うため保持せず、変換時に取得し直す
+    async fn prefetch_block_links(&self, pages: &[Page]) {
+        for page in pages {
+            if let Some(title) = self.extract_page_title(page) {
+                self.page_titles
+                    .lock()
+                    .unwrap()
+                    .insert(compact_id(&page.id), self.sanitize_filename(&title));
+            }
+
+            match fetch_block_tree(&self.client, &page.id).await {
+                Ok(tree) => {
+                    self.referenced_blocks
+                        .lock()
+                        .unwrap()
+                        .extend(anchors::block_links(&tree).into_iter().map(|l| l.block_id));
+                    self.scan_headings(&page.id, &tree);
+                }
+                Err(e) => warn!("{}", tr!(Msg::BlockFetchFailed, page.id, e)),
+            }
+        }
+    }
+
+    // 参照されているブロックに ^id を付けるよう設定する
+    fn anchor_blocks(&self, tree: &[BlockNode], options: &mut RenderOptions) {
+        let mut referenced = self.referenced_blocks.lock().unwrap();
+        referenced.extend(anchors::block_links(tree).into_iter().map(|l| l.block_id));
+        BlockNode::walk(tree, &mut |node| {
+            let block_id = compact_id(node.id());
+            if referenced.contains(&block_id) {
+                options.anchors.insert(block_id);
+            }
+        });
+    }
+
+    // ブロックへのリンクをWikiリンクにする
+    async fn link_blocks(&self, page_id: &str, tree: &[BlockNode], content: String) -> String {
+        self.scan_headings(page_id, tree);
+        let mut titles = HashMap::new();
+        let mut headings = HashMap::new();
+        for link in anchors::block_links(tree) {
+            if let Some(heading) = self.heading(&link).await {
+                headings.insert(link.block_id.clone(), heading);
+            }
+            if titles.contains_key(&link.page_id) {
+                continue;
+            }
+            match self.page_title(&link.page_id).await {
+                Ok(title) => {
+                    titles.insert(link.page_id.clone(), title);
+                }
+                Err(e) => warn!("{}", tr!(Msg::LinkedPageFetchFailed, link.page_id, e)),
+            }
+        }
+
+        anchors::rewrite_links(&content, &titles, &headings)
+    }
+
+    fn scan_headings(&self, page_id: &str, tree: &[BlockNode]) {
+        self.headings.lock().unwrap().extend(
+            anchors::headings(tree)
+                .into_iter()
+                .map(|(block_id, text)| (block_id, Some(text))),
+        );
+        self.scanned_pages
+            .lock()
+            .unwrap()
+            .insert(compact_id(page_id));
+    }
+
+    // リンク先のブロックが見出しならリンクに使うテキストを返す
+    // 見出しを集めていないページ（変換対象外など）のブロックは取得して確認する
+    async fn heading(&self, link: &BlockLink) -> Option<String> {
+        let known = self.headings.lock().unwrap().get(&link.block_id).cloned();
+        if let Some(heading) = known {
+            return heading;
+        }
+        if self.scanned_pages.lock().unwrap().contains(&link.page_id) {
+            return None;
+        }
+        let heading = match self.client.blocks.retrieve_a_block(&link.block_id).await {
+            Ok(block) => anchors::heading_text(&block),
+            Err(e) => {
+                warn!("{}", tr!(Msg::BlockFetchFailed, link.block_id, e));
+                None
+            }
+        };
+        self.headings
+            .lock()
+            .unwrap()
+            .insert(link.block_id.clone(), heading.clone());
+        heading
+    }
+
+    async fn page_title(&self, page_id: &str) -> Result<String> {
+        // 名前を変更されたノートはそのファイル名にリンクする
+        if let Some(stem) = self
+            .existing_note(page_id)
+            .as_deref()
+            .and_then(Path::file_stem)
+        {
+            return Ok(stem.to_string_lossy().to_string());
+        }
+        if let Some(title) = self.page_titles.lock().unwrap().get(page_id) {
+            return Ok(title.clone());
+        }
+
+        let page = self
+            .client
+            .pages
+            .retrieve_a_page(page_id, None)
+            .await
+            .map_err(|e| NotionToObsidianError::page_retrieval(page_id, e))?;
+        let title = self.sanitize_filename(
+            &self
+                .extract_page_title(&page)
+                .unwrap_or_else(|| "Untitled".to_string()),
+        );
+        self.page_titles
+            .lock()
+            .unwrap()
+            .insert(page_id.to_string(), title.clone());
+        Ok(title)
+    }
+
+    // 同期ブロックを埋め込みに置き換えるよう設定し、ノートモードではオリジナルの内容をノートに書き出す
+    // 内容を取得できない参照（共有されていないなど）はそのままにする
+    async fn prepare_synced_blocks(
+        &self,
+        tree: &[BlockNode],
+        options: &mut RenderOptions,
+    ) -> Result<()> {
+        let synced_blocks: Vec<_> = synced::synced_blocks(tree)
+            .into_iter()
+            .filter(|synced_block| !synced_block.children.is_empty())
+            .collect();
+
+        for synced_block in &synced_blocks {
+            let block_id = compact_id(&synced_block.block_id);
+            match &self.options.synced_block_mode {
+                SyncedBlockMode::Inline => {}
+                SyncedBlockMode::Note { .. } => {
+                    let name = synced::note_name(&synced_block.source_id);
+                    options
+                        .synced_embeds
+                        .insert(block_id, synced::embed(&name, None));
+                }
+                SyncedBlockMode::Anchor if synced_block.is_original() => {
+                    options.anchors.insert(block_id);
+                }
+                SyncedBlockMode::Anchor => {
+                    let origin = self.synced_origin_title(&synced_block.source_id).await?;
+                    let anchor = compact_id(&synced_block.source_id);
+                    options
+                        .synced_embeds
+                        .insert(block_id, synced::embed(&origin, Some(&anchor)));
+                }
+            }
+        }
+
+        // 入れ子の同期ブロックも埋め込みになるよう、設定がそろってから書き出す
+        if let SyncedBlockMode::Note { folder } = &self.options.synced_block_mode {
+            for synced_block in &synced_blocks {
+                let name = synced::note_name(&synced_block.source_id);
+                let content = self.renderer.render_with(synced_block.children, options);
+                self.export_synced_note(folder, &name, &synced_block.source_id, content.trim())?;
+            }
+        }
+        Ok(())
+    }
+
+    // ページのコメントはセクションまたは別ノートに出力し、ブロックのコメントの脚注を末尾に追加する
+    fn append_comments(
+        &self,
+        page: &Page,
+        mut content: String,
+        page_entries: &[CommentEntry],
+        footnotes: &[String],
+    ) -> Result<String> {
+        if !page_entries.is_empty() {
+            let section = comments::render_section(page_entries);
+            match self.options.comments_mode {
+                CommentsMode::Section => {
+                    content = format!("{}\n\n{}", content.trim_end(), section);
+                }
+                CommentsMode::Sidecar => {
+                    let title = self.sanitize_filename(
+                        &self
+                            .extract_page_title(page)
+                            .unwrap_or_else(|| "Untitled".to_string()),
+                    );
+                    let filepath = self.obsidian_dir.join(format!("{} Comments.md", title));
+                    vault::write_atomic(&filepath, format!("[[{}]]\n\n{}", title, section))?;
+                }
+                CommentsMode::Disabled => {}
+            }
+        }
+
+        if !footnotes.is_empty() {
+            content = format!("{}\n\n{}\n", content.trim_end(), footnotes.join("\n"));
+        }
+
+        Ok(content)
+    }
+
+    // 本文でメンションされたユーザーとPeopleプロパティのユーザーの人物ノートを作成する
+    fn write_user_stubs(
+        &self,
+        user_directory: &UserDirectory,
+        page: &Page,
+        tree: &[BlockNode],
+    ) -> Result<()> {
+        let mentioned = users::mentioned_users(tree);
+        let people = users::page_people(page);
+        for user in mentioned
+            .iter()
+            .map(|(_, user)| *user)
+            .chain(people.iter().flat_map(|(_, users)| users.iter().copied()))
+        {
+            user_directory.write_stub(&self.obsidian_dir, user)?;
+        }
+        Ok(())
+    }
+
+    fn export_synced_note(
+        &self,
+        folder: &str,
+        name: &str,
+        source_id: &str,
+        content: &str,
+    ) -> Result<()> {
+        let mut exported = self.exported_synced_blocks.lock().unwrap();
+        if exported.contains(source_id) {
+            return Ok(());
+        }
+
+        let dir = self.obsidian_dir.join(folder);
+        fs::create_dir_all(&dir).map_err(|e| NotionToObsidianError::file_write(&dir, e))?;
+        vault::write_atomic(&dir.join(format!("{}.md", name)), format!("{}\n", content))?;
+
+        exported.insert(source_id.to_string());
+        Ok(())
+    }
+
+    async fn synced_origin_title(&self, source_id: &str) -> Result<String> {
+        let page_id = find_parent_page_id(&self.client, source_id).await?;
+        let page = self
+            .client
+            .pages
+            .retrieve_a_page(&page_id, None)
+            .await
+            .map_err(|e| NotionToObsidianError::page_retrieval(&page_id, e))?;
+
+        let title = self
+            .extract_page_title(&page)
+            .unwrap_or_else(|| "Untitled".to_string());
+        Ok(self.sanitize_filename(&title))
+    }
+
+    async fn generate_frontmatter(&self, page: &Page, client: &Client) -> String {
         self.frontmatter_generator
             .generate(page, client)
+            .await
             .unwrap_or_else(|e| {
-                info!("Frontmatterの生成に失敗: {}", e);
+                info!("{}", tr!(Msg::FrontmatterFailed, e));
                 String::new()
             })
     }
 
     pub fn extract_page_title(&self, page: &Page) -> Option<String> {
-        for (_, property) in &page.properties {
-            if let PageProperty::Title { title, .. } = property {
-                let title_text: String = title.iter().filter_map(|rt| rt.plain_text()).collect();
-                if !title_text.is_empty() {
-                    return Some(title_text);
-                }
-            }
-        }
-        None
+        extract_page_title(page)
     }
 
     pub fn sanitize_filename(&self, filename: &str) -> String {
-        let invalid_chars = Regex::new(r#"[/\\:*?"<>|]"#).unwrap();
-        let multiple_spaces = Regex::new(r"\s+").unwrap();
-
-        let sanitized = invalid_chars.replace_all(filename, "");
-        let sanitized = multiple_spaces.replace_all(&sanitized, " ");
-        sanitized.trim().to_string()
+        sanitize_filename(filename)
     }
 
     pub async fn save_to_file(&self, title: &str, content: &str) -> Result<()> {
         let filename = self.sanitize_filename(title);
         let filepath = self.obsidian_dir.join(format!("{}.md", filename));
 
-        fs::write(&filepath, content)
-            .map_err(|e| NotionToObsidianError::FileWriteError(e.to_string()))?;
+        vault::write_atomic(&filepath, content)
+    }
 
-        Ok(())
+    fn existing_note(&self, page_id: &str) -> Option<PathBuf> {
+        self.notes
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| vault::index_notes(&self.obsidian_dir))
+            .get(&compact_id(page_id))
+            .cloned()
     }
 
-    pub async fn migrate_pages(&self) -> Result<(usize, usize)> {
-        println!("ページの変換を開始します...");
+    // フロントマターのnotion_idが一致するノートがあれば、名前を変更されていてもそのノートに書き込む
+    fn note_path(&self, page: &Page, title: &str) -> PathBuf {
+        self.existing_note(&page.id).unwrap_or_else(|| {
+            self.obsidian_dir
+                .join(format!("{}.md", self.sanitize_filename(title)))
+        })
+    }
+
+    // タスクデータベースの全行を1つのプロジェクトノートに書き出す
+    pub async fn export_task_database(&self, config: &TaskDatabase) -> Result<usize> {
+        let mut lines = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let request = QueryDatabaseRequest {
+                start_cursor: cursor.clone(),
+                ..Default::default()
+            };
+            let response = self
+                .client
+                .databases
+                .query_a_database(&config.database_id, request)
+                .await
+                .map_err(|e| NotionToObsidianError::database_query(&config.database_id, e))?;
+
+            for page in &response.results {
+                let title = self
+                    .extract_page_title(page)
+                    .unwrap_or_else(|| "Untitled".to_string());
+                lines.push(tasks::task_entry(
+                    page,
+                    &title,
+                    config,
+                    self.options.user_directory.as_deref(),
+                ));
+            }
+
+            match response.next_cursor {
+                Some(next) if response.has_more => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        self.save_to_file(&config.note_title, &format!("{}\n", lines.join("\n")))
+            .await?;
+        Ok(lines.len())
+    }
 
+    // cancelで中止した場合は処理中のページまで変換し、残りをチェックポイントに記録する
+    // 中断した変換が残っている場合は、残りのページの記録を上書きしないようエラーにする
+    pub async fn migrate_pages(&self, cancel: &CancellationToken) -> Result<MigrationReport> {
+        if Checkpoint::exists(&self.obsidian_dir) {
+            return Err(NotionToObsidianError::CheckpointExists);
+        }
+        let retried = self.reconcile_journal().await;
         let pages = self.page_provider.get_pages(&self.client).await?;
-        println!("{} ページを取得しました。変換を開始します...", pages.len());
+        let checkpoint = Checkpoint::new(pages.iter().map(|page| page.id.clone()).collect());
+        self.run(pages, Vec::new(), checkpoint, retried, cancel)
+            .await
+    }
+
+    // 前回中断した一括変換の残りのページを変換する
+    pub async fn resume_pages(&self, cancel: &CancellationToken) -> Result<MigrationReport> {
+        let checkpoint =
+            Checkpoint::load(&self.obsidian_dir)?.ok_or(NotionToObsidianError::NoCheckpoint)?;
+        let retried = self.reconcile_journal().await;
+
+        // 取得できなかったページは失敗として報告し、チェックポイントに残して次回の再開で再試行する
+        let mut pages = Vec::new();
+        let mut unavailable = Vec::new();
+        for page_id in &checkpoint.pending {
+            match self.retrieve_page(page_id).await {
+                Ok(page) => pages.push(page),
+                Err(e) => unavailable.push((page_id.clone(), e)),
+            }
+        }
+        self.run(pages, unavailable, checkpoint, retried, cancel)
+            .await
+    }
+
+    async fn retrieve_page(&self, page_id: &str) -> Result<Page> {
+        match &self.export {
+            Some(export) => export.require_page(page_id)?.to_page(),
+            None => self
+                .client
+                .pages
+                .retrieve_a_page(page_id, None)
+                .await
+                .map_err(|e| NotionToObsidianError::page_retrieval(page_id, e)),
+        }
+    }
+
+    // unavailableは取得できなかったページ（ページID, エラー）
+    async fn run(
+        &self,
+        pages: Vec<Page>,
+        unavailable: Vec<(String, NotionToObsidianError)>,
+        mut checkpoint: Checkpoint,
+        retried: Vec<PageResult>,
+        cancel: &CancellationToken,
+    ) -> Result<MigrationReport> {
+        let mut report = MigrationReport {
+            retried,
+            ..Default::default()
+        };
+        checkpoint.save(&self.obsidian_dir)?;
+        self.observer.on_event(&MigrationEvent::PagesDiscovered {
+            count: pages.len() + unavailable.len(),
+        });
+
+        for (page_id, e) in unavailable {
+            self.observer.on_event(&MigrationEvent::PageStarted {
+                page_id: page_id.clone(),
+                title: page_id.clone(),
+            });
+            self.observer.on_event(&MigrationEvent::PageFailed {
+                page_id: page_id.clone(),
+                title: page_id.clone(),
+                error: e.to_string(),
+            });
+            report.pages.push(PageResult {
+                page_id: page_id.clone(),
+                title: page_id,
+                outcome: PageOutcome::Failed {
+                    error: e.to_string(),
+                },
+            });
+        }
+
+        // エクスポートの場合はブロックIDを含まないため、アンカーは付けられない
+        if self.options.block_anchors && self.export.is_none() {
+            self.prefetch_block_links(&pages).await;
+        }
 
-        let mut success_count = 0;
         for page in &pages {
+            if cancel.is_cancelled() {
+                report.cancelled = true;
+                break;
+            }
+
             let title = self
                 .extract_page_title(page)
                 .unwrap_or_else(|| "Untitled".to_string());
 
-            println!("ページ {} の変換を開始...", title);
+            self.observer.on_event(&MigrationEvent::PageStarted {
+                page_id: page.id.clone(),
+                title: title.clone(),
+            });
+
+            let outcome = self.migrate_page(page, &title).await;
+            report.pages.push(PageResult {
+                page_id: page.id.clone(),
+                title,
+                outcome,
+            });
+
+            checkpoint.complete(&page.id);
+            if let Err(e) = checkpoint.save(&self.obsidian_dir) {
+                warn!("{}", tr!(Msg::CheckpointSaveFailed, e));
+            }
+        }
+
+        if let Err(e) = self.frontmatter_generator.finish() {
+            warn!("{}", tr!(Msg::FrontmatterFinishFailed, e));
+        }
+
+        // 取得できなかったページが残っている場合はチェックポイントを残す
+        if !report.cancelled && checkpoint.pending.is_empty() {
+            Checkpoint::remove(&self.obsidian_dir)?;
+        }
+        self.observer.on_event(&MigrationEvent::RunFinished {
+            report: report.clone(),
+        });
+        Ok(report)
+    }
+
+    // 変換→書き込み→Notionへの反映の順に処理し、途中経過をジャーナルに記録する
+    async fn migrate_page(&self, page: &Page, title: &str) -> PageOutcome {
+        let converted = match &self.export {
+            Some(export) => self.convert_exported_page(export, page).await,
+            None => self.convert_page(&page.id).await,
+        };
+        let full_content = match converted {
+            Ok(full_content) => full_content,
+            Err(e) => return self.page_failed(page, title, e),
+        };
+
+        let note = self.note_path(page, title);
+        self.record_journal(
+            &page.id,
+            title,
+            &note,
+            JournalStage::Writing,
+            String::new(),
+            Vec::new(),
+        );
+        if let Err(e) = vault::write_atomic(&note, &full_content) {
+            self.remove_journal(&page.id);
+            return self.page_failed(page, title, e);
+        }
+        self.observer.on_event(&MigrationEvent::PageWritten {
+            page_id: page.id.clone(),
+            title: title.to_string(),
+            path: note.clone(),
+        });
 
-            match self.convert_page(&page.id).await {
-                Ok(full_content) => match self.save_to_file(&title, &full_content).await {
-                    Ok(_) => match self.post_processor.process(page, &self.client).await {
-                        Ok(_) => {
-                            success_count += 1;
-                            println!("ページを正常に変換しました: {}", title);
+        self.post_process(page, title, &note, Vec::new()).await
+    }
+
+    // 後処理に失敗した場合は完了した処理とともにジャーナルに残し、次回の実行で残りを再試行する
+    async fn post_process(
+        &self,
+        page: &Page,
+        title: &str,
+        note: &Path,
+        mut completed: Vec<String>,
+    ) -> PageOutcome {
+        match self
+            .post_processor
+            .process_steps(page, &self.client, note, &mut completed)
+            .await
+        {
+            Ok(_) => {
+                self.remove_journal(&page.id);
+                PageOutcome::Migrated
+            }
+            Err(e) => {
+                self.observer.on_event(&MigrationEvent::PostProcessFailed {
+                    page_id: page.id.clone(),
+                    title: title.to_string(),
+                    error: e.to_string(),
+                });
+                self.record_journal(
+                    &page.id,
+                    title,
+                    note,
+                    JournalStage::Marking,
+                    e.to_string(),
+                    completed,
+                );
+                PageOutcome::PartiallyMigrated {
+                    error: e.to_string(),
+                }
+            }
+        }
+    }
+
+    fn page_failed(&self, page: &Page, title: &str, e: NotionToObsidianError) -> PageOutcome {
+        self.observer.on_event(&MigrationEvent::PageFailed {
+            page_id: page.id.clone(),
+            title: title.to_string(),
+            error: e.to_string(),
+        });
+        PageOutcome::Failed {
+            error: e.to_string(),
+        }
+    }
+
+    fn record_journal(
+        &self,
+        page_id: &str,
+        title: &str,
+        note: &Path,
+        stage: JournalStage,
+        error: String,
+        completed: Vec<String>,
+    ) {
+        let entry = JournalEntry {
+            title: title.to_string(),
+            note: note.to_path_buf(),
+            stage,
+            error,
+            completed,
+        };
+        if let Err(e) = self.journal.lock().unwrap().record(page_id, entry) {
+            warn!("{}", tr!(Msg::JournalUpdateFailed, e));
+        }
+    }
+
+    fn remove_journal(&self, page_id: &str) {
+        if let Err(e) = self.journal.lock().unwrap().remove(page_id) {
+            warn!("{}", tr!(Msg::JournalUpdateFailed, e));
+        }
+    }
+
+    // 前回の実行で中断・失敗したページを整合させる
+    // - 書き込み中に中断したページは一時ファイルを削除する（Notionには反映していないため次の変換対象に残る）
+    // - Notionへの反映に失敗したページは後処理を再試行する
+    pub async fn reconcile_journal(&self) -> Vec<PageResult> {
+        let entries: Vec<(String, JournalEntry)> = self
+            .journal
+            .lock()
+            .unwrap()
+            .entries()
+            .iter()
+            .map(|(page_id, entry)| (page_id.clone(), entry.clone()))
+            .collect();
+
+        let mut results = Vec::new();
+        for (page_id, entry) in entries {
+            // エクスポートからの変換ではNotionに反映できないため、後処理は次のAPIでの実行まで残す
+            if self.export.is_some() && entry.stage == JournalStage::Marking {
+                continue;
+            }
+            self.observer.on_event(&MigrationEvent::PageStarted {
+                page_id: page_id.clone(),
+                title: entry.title.clone(),
+            });
+            let outcome = match entry.stage {
+                JournalStage::Writing => {
+                    let _ = fs::remove_file(vault::temp_path(&entry.note));
+                    self.remove_journal(&page_id);
+                    let error = tr!(Msg::WriteInterrupted);
+                    self.observer.on_event(&MigrationEvent::PageFailed {
+                        page_id: page_id.clone(),
+                        title: entry.title.clone(),
+                        error: error.clone(),
+                    });
+                    PageOutcome::Failed { error }
+                }
+                // ノートは書き込み済みのため、後処理の結果だけを通知する
+                JournalStage::Marking => {
+                    let note = self.existing_note(&page_id).unwrap_or(entry.note);
+                    self.observer.on_event(&MigrationEvent::PageWritten {
+                        page_id: page_id.clone(),
+                        title: entry.title.clone(),
+                        path: note.clone(),
+                    });
+                    match self.client.pages.retrieve_a_page(&page_id, None).await {
+                        Ok(page) => {
+                            self.post_process(&page, &entry.title, &note, entry.completed)
+                                .await
                         }
                         Err(e) => {
-                            eprintln!("移行済みフラグの更新に失敗: {}", e);
+                            let error =
+                                NotionToObsidianError::page_retrieval(&page_id, e).to_string();
+                            self.observer.on_event(&MigrationEvent::PostProcessFailed {
+                                page_id: page_id.clone(),
+                                title: entry.title.clone(),
+                                error: error.clone(),
+                            });
+                            PageOutcome::PartiallyMigrated { error }
                         }
-                    },
-                    Err(e) => {
-                        eprintln!("ファイルの保存に失敗: {}", e);
                     }
-                },
-                Err(e) => {
-                    eprintln!("ページの変換に失敗: {}", e);
                 }
-            }
+            };
+            results.push(PageResult {
+                page_id,
+                title: entry.title,
+                outcome,
+            });
         }
+        results
+    }
+}
 
-        Ok((success_count, pages.len()))
+pub fn extract_page_title(page: &Page) -> Option<String> {
+    for property in page.properties.values() {
+        if let PageProperty::Title { title, .. } = property {
+            let title_text: String = title.iter().filter_map(|rt| rt.plain_text()).collect();
+            if !title_text.is_empty() {
+                return Some(title_text);
+            }
+        }
     }
-}
\ No newline at end of file
+    None
+}
+
+pub fn sanitize_filename(filename: &str) -> String {
+    let invalid_chars = Regex::new(r#"[/\\:*?"<>|]"#).unwrap();
+    let multiple_spaces = Regex::new(r"\s+").unwrap();
+
+    let sanitized = invalid_chars.replace_all(filename, "");
+    let sanitized = multiple_spaces.replace_all(&sanitized, " ");
+    sanitized.trim().to_string()
+}
@@ -0,0 +1,142 @@
+use chrono::{Local, NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use notion_client::objects::{
+    page::{DateOrDateTime, DatePropertyValue, Page, PageProperty},
+    property,
+};
+
+use crate::traits::frontmatter_generator::yaml_string;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateStyle {
+    // 2025-03-06 / 2025-03-06T17:40
+    Iso,
+    // [[2025-03-06]]（formatはデイリーノートのファイル名の書式）
+    DailyNote { format: String },
+    // Tasksプラグインの期日 📅 2025-03-06
+    TasksDue,
+}
+
+impl DateStyle {
+    pub fn daily_note() -> DateStyle {
+        DateStyle::DailyNote {
+            format: "%Y-%m-%d".to_string(),
+        }
+    }
+}
+
+type LocalDate = (NaiveDate, Option<NaiveTime>);
+
+// 日時はページで指定されたタイムゾーン（なければローカル）に変換する
+fn localize(value: &DateOrDateTime, time_zone: Option<&str>) -> LocalDate {
+    match value {
+        DateOrDateTime::Date(date) => (*date, None),
+        DateOrDateTime::DateTime(datetime) => {
+            let local = match time_zone.and_then(|tz| tz.parse::<Tz>().ok()) {
+                Some(tz) => datetime.with_timezone(&tz).naive_local(),
+                None => datetime.with_timezone(&Local).naive_local(),
+            };
+            (local.date(), Some(local.time()))
+        }
+    }
+}
+
+fn localize_range(value: &DatePropertyValue) -> Option<(LocalDate, Option<LocalDate>)> {
+    let time_zone = value.time_zone.as_deref();
+    let start = localize(value.start.as_ref()?, time_zone);
+    let end = value.end.as_ref().map(|end| localize(end, time_zone));
+    Some((start, end))
+}
+
+// 開始日（日時の場合はタイムゾーンを変換した日付）
+pub fn start_date(value: &DatePropertyValue) -> Option<NaiveDate> {
+    localize_range(value).map(|((date, _), _)| date)
+}
+
+fn iso((date, time): LocalDate) -> String {
+    match time {
+        Some(time) => format!("{}T{}", date.format("%Y-%m-%d"), time.format("%H:%M")),
+        None => date.format("%Y-%m-%d").to_string(),
+    }
+}
+
+fn daily_note_link((date, time): LocalDate, format: &str) -> String {
+    let link = format!("[[{}]]", date.format(format));
+    match time {
+        Some(time) => format!("{} {}", link, time.format("%H:%M")),
+        None => link,
+    }
+}
+
+// 本文用の表記。期間は「開始 → 終了」で表す
+pub fn render(value: &DatePropertyValue, style: &DateStyle) -> Option<String> {
+    let (start, end) = localize_range(value)?;
+
+    let rendered = match style {
+        DateStyle::Iso => match end {
+            Some(end) => format!("{} → {}", iso(start), iso(end)),
+            None => iso(start),
+        },
+        DateStyle::DailyNote { format } => match end {
+            Some(end) => format!(
+                "{} → {}",
+                daily_note_link(start, format),
+                daily_note_link(end, format)
+            ),
+            None => daily_note_link(start, format),
+        },
+        DateStyle::TasksDue => match end {
+            Some(end) => format!(
+                "🛫 {} 📅 {}",
+                start.0.format("%Y-%m-%d"),
+                end.0.format("%Y-%m-%d")
+            ),
+            None => format!("📅 {}", start.0.format("%Y-%m-%d")),
+        },
+    };
+
+    Some(rendered)
+}
+
+// 日付メンションはnotion_client 1.0では日時のみの別の型になっているため、プロパティの値にそろえて表記する
+pub fn render_mention(value: &property::DatePropertyValue, style: &DateStyle) -> Option<String> {
+    let value = DatePropertyValue {
+        start: Some(DateOrDateTime::DateTime(value.start)),
+        end: value.end.map(DateOrDateTime::DateTime),
+        time_zone: value.time_zone.clone(),
+    };
+    render(&value, style)
+}
+
+// フロントマター用の表記。期間の終了日は「<プロパティ名>_end」に出力する
+pub fn frontmatter(page: &Page, style: &DateStyle) -> String {
+    let mut dates: Vec<(&String, &DatePropertyValue)> = page
+        .properties
+        .iter()
+        .filter_map(|(name, prop)| match prop {
+            PageProperty::Date {
+                date: Some(date), ..
+            } => Some((name, date)),
+            _ => None,
+        })
+        .collect();
+    dates.sort_by_key(|(name, _)| *name);
+
+    let format_value = |date: LocalDate| match style {
+        DateStyle::DailyNote { format } => format!("\"{}\"", daily_note_link(date, format)),
+        _ => iso(date),
+    };
+
+    let mut frontmatter = String::new();
+    for (name, value) in dates {
+        let Some((start, end)) = localize_range(value) else {
+            continue;
+        };
+        frontmatter.push_str(&format!("{}: {}\n", yaml_string(name), format_value(start)));
+        if let Some(end) = end {
+            let key = yaml_string(&format!("{}_end", name));
+            frontmatter.push_str(&format!("{}: {}\n", key, format_value(end)));
+        }
+    }
+    frontmatter
+}
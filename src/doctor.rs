@@ -0,0 +1,302 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use notion_client::endpoints::Client;
+use serde::Serialize;
+
+use crate::{
+    error::{ErrorKind, NotionToObsidianError},
+    i18n::Msg,
+    tr,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    // 変換はできるが確認できなかった・一部の機能が使えない
+    Warning,
+    Error,
+}
+
+// 診断の結果（fixは問題がある場合の対処方法）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub status: CheckStatus,
+    pub title: String,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(title: String, detail: String) -> Self {
+        Self {
+            status: CheckStatus::Ok,
+            title,
+            detail,
+            fix: None,
+        }
+    }
+
+    fn warning(title: String, detail: String, fix: Option<String>) -> Self {
+        Self {
+            status: CheckStatus::Warning,
+            title,
+            detail,
+            fix,
+        }
+    }
+
+    fn error(title: String, detail: String, fix: Option<String>) -> Self {
+        Self {
+            status: CheckStatus::Error,
+            title,
+            detail,
+            fix,
+        }
+    }
+}
+
+// データベースに必要なプロパティ（typesはNotion APIのtype名のいずれか）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyRequirement {
+    pub name: String,
+    pub types: Vec<String>,
+}
+
+impl PropertyRequirement {
+    pub fn new(name: &str, types: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            types: types.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+}
+
+struct DatabaseTarget {
+    // 環境変数名など、ユーザーが設定を探せる名前
+    label: String,
+    id: Option<String>,
+    properties: Vec<PropertyRequirement>,
+}
+
+// トークン・データベースの共有・プロパティ・出力先を確認する
+pub struct Doctor {
+    token: Option<String>,
+    databases: Vec<DatabaseTarget>,
+    output_dir: Option<PathBuf>,
+}
+
+impl Doctor {
+    pub fn new(token: Option<String>) -> Self {
+        Self {
+            token: token.filter(|token| !token.is_empty()),
+            databases: Vec::new(),
+            output_dir: None,
+        }
+    }
+
+    pub fn with_database(
+        mut self,
+        label: &str,
+        id: Option<String>,
+        properties: Vec<PropertyRequirement>,
+    ) -> Doctor {
+        self.databases.push(DatabaseTarget {
+            label: label.to_string(),
+            id: id.filter(|id| !id.is_empty()),
+            properties,
+        });
+        self
+    }
+
+    pub fn with_output_dir(self, output_dir: Option<PathBuf>) -> Doctor {
+        Doctor { output_dir, ..self }
+    }
+
+    pub async fn run(&self) -> Vec<Check> {
+        let mut checks = Vec::new();
+
+        let client = self.check_token(&mut checks).await;
+        for database in &self.databases {
+            checks.extend(check_database(client.as_ref(), database).await);
+        }
+        checks.push(match &self.output_dir {
+            Some(dir) => check_output_dir(dir),
+            None => Check::error(
+                tr!(Msg::DoctorOutputDir),
+                tr!(Msg::DoctorEnvVarMissing, "OBSIDIAN_DIR"),
+                Some(tr!(Msg::DoctorOutputDirFix)),
+            ),
+        });
+        checks
+    }
+
+    // 認証できた場合のみクライアントを返す
+    async fn check_token(&self, checks: &mut Vec<Check>) -> Option<Client> {
+        let title = tr!(Msg::DoctorToken);
+        let Some(token) = &self.token else {
+            checks.push(Check::error(
+                title,
+                tr!(Msg::DoctorEnvVarMissing, "NOTION_TOKEN"),
+                Some(tr!(Msg::DoctorTokenFix)),
+            ));
+            return None;
+        };
+        let client = match Client::new(token.clone(), None) {
+            Ok(client) => client,
+            Err(e) => {
+                checks.push(Check::error(
+                    title,
+                    NotionToObsidianError::api(e).to_string(),
+                    Some(tr!(Msg::DoctorTokenFix)),
+                ));
+                return None;
+            }
+        };
+
+        match client.users.list_all_users(None, None).await {
+            Ok(_) => {
+                checks.push(Check::ok(title, tr!(Msg::DoctorTokenOk)));
+                Some(client)
+            }
+            Err(e) => {
+                let error = NotionToObsidianError::api(e);
+                match error.kind() {
+                    ErrorKind::Unauthorized => {
+                        checks.push(Check::error(
+                            title,
+                            error.to_string(),
+                            Some(tr!(Msg::DoctorTokenFix)),
+                        ));
+                        None
+                    }
+                    // ユーザー情報の権限がないだけでトークンは有効
+                    ErrorKind::PermissionDenied => {
+                        checks.push(Check::warning(
+                            title,
+                            error.to_string(),
+                            Some(tr!(Msg::DoctorUserCapabilityFix)),
+                        ));
+                        Some(client)
+                    }
+                    _ => {
+                        checks.push(Check::error(title, error.to_string(), retry_fix(&error)));
+                        None
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn retry_fix(error: &NotionToObsidianError) -> Option<String> {
+    error.is_retryable().then(|| tr!(Msg::DoctorRetryFix))
+}
+
+async fn check_database(client: Option<&Client>, target: &DatabaseTarget) -> Vec<Check> {
+    let title = tr!(Msg::DoctorDatabase, target.label);
+    let Some(id) = &target.id else {
+        return vec![Check::error(
+            title,
+            tr!(Msg::DoctorEnvVarMissing, target.label),
+            Some(tr!(Msg::DoctorDatabaseIdFix, target.label)),
+        )];
+    };
+    // トークンの確認に失敗した場合は確認できない
+    let Some(client) = client else {
+        return vec![Check::warning(title, tr!(Msg::DoctorSkipped), None)];
+    };
+
+    // ページのないデータベースも確認できるよう、スキーマのプロパティを参照する
+    let database = match client.databases.retrieve_a_database(id).await {
+        Ok(database) => database,
+        Err(e) => {
+            let error = NotionToObsidianError::database_query(id, e);
+            let fix = match error.kind() {
+                ErrorKind::NotFound | ErrorKind::PermissionDenied => Some(tr!(Msg::DoctorShareFix)),
+                _ => retry_fix(&error),
+            };
+            return vec![Check::error(title, error.to_string(), fix)];
+        }
+    };
+
+    let mut checks = vec![Check::ok(title, tr!(Msg::DoctorDatabaseOk, id))];
+    checks.extend(check_properties(
+        &target.label,
+        &property_types(&database.properties),
+        &target.properties,
+    ));
+    checks
+}
+
+// プロパティ名からtype名への対応（データベースのスキーマ・ページのどちらのプロパティにも使える）
+pub fn property_types<P: Serialize>(properties: &HashMap<String, P>) -> BTreeMap<String, String> {
+    properties
+        .iter()
+        .filter_map(|(name, property)| {
+            let value = serde_json::to_value(property).ok()?;
+            let kind = value.get("type")?.as_str()?.to_string();
+            Some((name.clone(), kind))
+        })
+        .collect()
+}
+
+pub fn check_properties(
+    database: &str,
+    types: &BTreeMap<String, String>,
+    requirements: &[PropertyRequirement],
+) -> Vec<Check> {
+    requirements
+        .iter()
+        .map(|requirement| {
+            let title = tr!(Msg::DoctorProperty, requirement.name, database);
+            let expected = requirement.types.join(" / ");
+            match types.get(&requirement.name) {
+                Some(kind) if requirement.types.contains(kind) => Check::ok(title, kind.clone()),
+                Some(kind) => Check::error(
+                    title,
+                    tr!(Msg::DoctorPropertyWrongType, kind, expected),
+                    Some(tr!(Msg::DoctorPropertyTypeFix, requirement.name, expected)),
+                ),
+                None => Check::error(
+                    title,
+                    tr!(Msg::DoctorPropertyMissing),
+                    Some(tr!(Msg::DoctorPropertyAddFix, expected, requirement.name)),
+                ),
+            }
+        })
+        .collect()
+}
+
+// 一時ファイルを作成・削除して書き込めるか確認する
+pub fn check_output_dir(dir: &Path) -> Check {
+    let title = tr!(Msg::DoctorOutputDir);
+    // 変換ではフォルダを作成しないため、ノートを書き込めない
+    if !dir.exists() {
+        return Check::error(
+            title,
+            tr!(Msg::DoctorOutputDirMissing, dir.display()),
+            Some(tr!(Msg::DoctorOutputDirFix)),
+        );
+    }
+    if !dir.is_dir() {
+        return Check::error(
+            title,
+            tr!(Msg::DoctorOutputDirNotDir, dir.display()),
+            Some(tr!(Msg::DoctorOutputDirFix)),
+        );
+    }
+
+    let probe = dir.join(".notion-to-obsidian-doctor.tmp");
+    match fs::write(&probe, b"").and_then(|_| fs::remove_file(&probe)) {
+        Ok(_) => Check::ok(title, dir.display().to_string()),
+        Err(e) => Check::error(
+            title,
+            NotionToObsidianError::file_write(&probe, e).to_string(),
+            Some(tr!(Msg::DoctorPermissionFix, dir.display())),
+        ),
+    }
+}
@@ -1,21 +1,366 @@
+use std::{error::Error as StdError, fmt, io, path::PathBuf};
+
+use notion_client::NotionClientError;
 use thiserror::Error;
 
+use crate::{i18n::Msg, tr};
+
+pub type BoxError = Box<dyn StdError + Send + Sync>;
+
+// エラーの分類（リトライするか、ユーザーに何を確認してもらうかの判断に使う）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    RateLimited,
+    Unauthorized,
+    PermissionDenied,
+    NotFound,
+    ServerError,
+    Network,
+    Io,
+    Other,
+}
+
+// Notion APIの呼び出しの失敗（元のエラーとHTTPステータスを保持する）
+#[derive(Debug)]
+pub struct NotionApiError {
+    pub status: Option<u16>,
+    source: BoxError,
+}
+
+impl NotionApiError {
+    pub fn new(source: impl Into<BoxError>) -> Self {
+        let source = source.into();
+        Self {
+            status: status_of(source.as_ref()),
+            source,
+        }
+    }
+
+    // notion_clientを使わずに呼び出したAPIのエラーレスポンス
+    pub fn with_status(status: u16, source: impl Into<BoxError>) -> Self {
+        Self {
+            status: Some(status),
+            source: source.into(),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self.status {
+            Some(429) => ErrorKind::RateLimited,
+            Some(401) => ErrorKind::Unauthorized,
+            Some(403) => ErrorKind::PermissionDenied,
+            Some(404) => ErrorKind::NotFound,
+            Some(500..=599) => ErrorKind::ServerError,
+            Some(_) => ErrorKind::Other,
+            None if is_network_error(self.source.as_ref()) => ErrorKind::Network,
+            None => ErrorKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for NotionApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.status {
+            Some(status) => write!(f, "HTTP {}: {}", status, self.source),
+            None => write!(f, "{}", self.source),
+        }
+    }
+}
+
+impl StdError for NotionApiError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+fn reqwest_error<'a>(error: &'a (dyn StdError + 'static)) -> Option<&'a reqwest::Error> {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+            return Some(error);
+        }
+        current = error.source();
+    }
+    None
+}
+
+// APIのエラーレスポンスはnotion_clientのInvalidStatusCode、通信時のエラーはreqwestのステータスを参照する
+fn status_of(error: &(dyn StdError + 'static)) -> Option<u16> {
+    if let Some(NotionClientError::InvalidStatusCode { error }) =
+        error.downcast_ref::<NotionClientError>()
+    {
+        return u16::try_from(error.status).ok();
+    }
+    reqwest_error(error)
+        .and_then(reqwest::Error::status)
+        .map(|status| status.as_u16())
+}
+
+fn is_network_error(error: &(dyn StdError + 'static)) -> bool {
+    reqwest_error(error)
+        .map(|e| e.is_timeout() || e.is_connect() || e.is_request())
+        .unwrap_or(false)
+}
+
+// メッセージは言語の設定（i18n）に従う。スクリプトからはcode()で判別する
+// メッセージに元のエラーを含むため、source()を辿って表示すると原因が重複する
 #[derive(Error, Debug)]
 pub enum NotionToObsidianError {
-    #[error("APIトークンが設定されていません")]
+    #[error("{}", self.message())]
     NoToken,
-    #[error("Notionのブロックの取得に失敗しました: {0}")]
-    BlockRetrievalError(String),
-    #[error("Notionのページの取得に失敗しました: {0}")]
-    PageRetrievalError(String),
-    #[error("変換処理に失敗しました: {0}")]
+    // クライアントの作成やユーザー一覧の取得など、特定のページ・ブロックに紐づかない呼び出し
+    #[error("{}", self.message())]
+    ApiError(#[source] NotionApiError),
+    #[error("{}", self.message())]
+    BlockRetrievalError {
+        block_id: String,
+        #[source]
+        source: NotionApiError,
+    },
+    #[error("{}", self.message())]
+    PageRetrievalError {
+        page_id: String,
+        #[source]
+        source: NotionApiError,
+    },
+    #[error("{}", self.message())]
+    DatabaseQueryError {
+        database_id: String,
+        #[source]
+        source: NotionApiError,
+    },
+    #[error("{}", self.message())]
+    CommentRetrievalError {
+        block_id: String,
+        #[source]
+        source: NotionApiError,
+    },
+    // プロパティの更新・コメントの追加など、ページへの書き込み
+    #[error("{}", self.message())]
+    PageUpdateError {
+        page_id: String,
+        #[source]
+        source: NotionApiError,
+    },
+    #[error("{}", self.message())]
+    ParentPageNotFound { block_id: String },
+    #[error("{}", self.message())]
     ConversionError(String),
-    #[error("ファイルの書き込みに失敗しました: {0}")]
-    FileWriteError(String),
-    #[error("環境変数が設定されていません: {0}")]
+    #[error("{}", self.message())]
+    DownloadError {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("{}", self.message())]
+    FileWriteError {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{}", self.message())]
+    FileReadError {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    // stepは失敗した後処理の名前（PostProcessor::name）
+    #[error("{}", self.message())]
+    PostProcessError {
+        step: String,
+        #[source]
+        source: BoxError,
+    },
+    #[error("{}", self.message())]
     EnvVarError(String),
-    #[error("タイトルが設定されていません")]
-    NoTitleError,
+    #[error("{}", self.message())]
+    NoCheckpoint,
+    // 中断した変換が残っている状態で新しく一括変換を始めようとした
+    #[error("{}", self.message())]
+    CheckpointExists,
+    // エクスポートのzipに含まれないページ（pathはzipのパス）
+    #[error("{}", self.message())]
+    PageNotInExport { page_id: String, path: PathBuf },
+}
+
+impl NotionToObsidianError {
+    pub fn api(source: impl Into<BoxError>) -> Self {
+        Self::ApiError(NotionApiError::new(source))
+    }
+
+    pub fn block_retrieval(block_id: &str, source: impl Into<BoxError>) -> Self {
+        Self::BlockRetrievalError {
+            block_id: block_id.to_string(),
+            source: NotionApiError::new(source),
+        }
+    }
+
+    pub fn page_retrieval(page_id: &str, source: impl Into<BoxError>) -> Self {
+        Self::PageRetrievalError {
+            page_id: page_id.to_string(),
+            source: NotionApiError::new(source),
+        }
+    }
+
+    pub fn database_query(database_id: &str, source: impl Into<BoxError>) -> Self {
+        Self::DatabaseQueryError {
+            database_id: database_id.to_string(),
+            source: NotionApiError::new(source),
+        }
+    }
+
+    pub fn comment_retrieval(block_id: &str, source: impl Into<BoxError>) -> Self {
+        Self::CommentRetrievalError {
+            block_id: block_id.to_string(),
+            source: NotionApiError::new(source),
+        }
+    }
+
+    pub fn page_update(page_id: &str, source: impl Into<BoxError>) -> Self {
+        Self::PageUpdateError {
+            page_id: page_id.to_string(),
+            source: NotionApiError::new(source),
+        }
+    }
+
+    // serde_jsonのエラーもio::Errorに変換できる
+    pub fn file_write(path: impl Into<PathBuf>, source: impl Into<io::Error>) -> Self {
+        Self::FileWriteError {
+            path: path.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn file_read(path: impl Into<PathBuf>, source: impl Into<io::Error>) -> Self {
+        Self::FileReadError {
+            path: path.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn post_process(step: &str, source: impl Into<BoxError>) -> Self {
+        Self::PostProcessError {
+            step: step.to_string(),
+            source: source.into(),
+        }
+    }
+
+    // 言語によらない識別子
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NoToken => "NO_TOKEN",
+            Self::ApiError(_) => "NOTION_API",
+            Self::BlockRetrievalError { .. } => "BLOCK_RETRIEVAL",
+            Self::PageRetrievalError { .. } => "PAGE_RETRIEVAL",
+            Self::DatabaseQueryError { .. } => "DATABASE_QUERY",
+            Self::CommentRetrievalError { .. } => "COMMENT_RETRIEVAL",
+            Self::PageUpdateError { .. } => "PAGE_UPDATE",
+            Self::ParentPageNotFound { .. } => "PARENT_PAGE_NOT_FOUND",
+            Self::ConversionError(_) => "CONVERSION",
+            Self::DownloadError { .. } => "DOWNLOAD",
+            Self::FileWriteError { .. } => "FILE_WRITE",
+            Self::FileReadError { .. } => "FILE_READ",
+            Self::PostProcessError { .. } => "POST_PROCESS",
+            Self::EnvVarError(_) => "ENV_VAR",
+            Self::NoCheckpoint => "NO_CHECKPOINT",
+            Self::CheckpointExists => "CHECKPOINT_EXISTS",
+            Self::PageNotInExport { .. } => "PAGE_NOT_IN_EXPORT",
+        }
+    }
+
+    fn api_error(&self) -> Option<&NotionApiError> {
+        match self {
+            Self::ApiError(source)
+            | Self::BlockRetrievalError { source, .. }
+            | Self::PageRetrievalError { source, .. }
+            | Self::DatabaseQueryError { source, .. }
+            | Self::CommentRetrievalError { source, .. }
+            | Self::PageUpdateError { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+
+    // 後処理のエラーは元のエラーを参照する
+    fn inner(&self) -> Option<&NotionToObsidianError> {
+        match self {
+            Self::PostProcessError { source, .. } => source.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::DownloadError { source, .. } => source.status().map(|s| s.as_u16()),
+            _ => self
+                .api_error()
+                .and_then(|e| e.status)
+                .or_else(|| self.inner().and_then(Self::status)),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        if let Some(api_error) = self.api_error() {
+            return api_error.kind();
+        }
+        if let Some(inner) = self.inner() {
+            return inner.kind();
+        }
+        match self {
+            Self::NoToken => ErrorKind::Unauthorized,
+            Self::ParentPageNotFound { .. } | Self::PageNotInExport { .. } => ErrorKind::NotFound,
+            Self::FileWriteError { .. } | Self::FileReadError { .. } => ErrorKind::Io,
+            Self::DownloadError { source, .. } => match source.status().map(|s| s.as_u16()) {
+                Some(404) => ErrorKind::NotFound,
+                Some(500..=599) => ErrorKind::ServerError,
+                Some(_) => ErrorKind::Other,
+                None => ErrorKind::Network,
+            },
+            _ => ErrorKind::Other,
+        }
+    }
+
+    // レート制限・サーバーエラー・通信エラーは時間をおけば成功する可能性がある
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::RateLimited | ErrorKind::ServerError | ErrorKind::Network
+        )
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Self::NoToken => tr!(Msg::NoToken),
+            Self::ApiError(source) => tr!(Msg::NotionApi, source),
+            Self::BlockRetrievalError { block_id, source } => {
+                tr!(Msg::BlockRetrieval, block_id, source)
+            }
+            Self::PageRetrievalError { page_id, source } => {
+                tr!(Msg::PageRetrieval, page_id, source)
+            }
+            Self::DatabaseQueryError {
+                database_id,
+                source,
+            } => tr!(Msg::DatabaseQuery, database_id, source),
+            Self::CommentRetrievalError { block_id, source } => {
+                tr!(Msg::CommentRetrieval, block_id, source)
+            }
+            Self::PageUpdateError { page_id, source } => tr!(Msg::PageUpdate, page_id, source),
+            Self::ParentPageNotFound { block_id } => tr!(Msg::ParentPageNotFound, block_id),
+            Self::ConversionError(e) => tr!(Msg::Conversion, e),
+            Self::DownloadError { url, source } => tr!(Msg::Download, url, source),
+            Self::FileWriteError { path, source } => {
+                tr!(Msg::FileWrite, path.display(), source)
+            }
+            Self::FileReadError { path, source } => tr!(Msg::FileRead, path.display(), source),
+            Self::PostProcessError { step, source } => tr!(Msg::PostProcess, step, source),
+            Self::EnvVarError(name) => tr!(Msg::EnvVar, name),
+            Self::NoCheckpoint => tr!(Msg::NoCheckpoint),
+            Self::CheckpointExists => tr!(Msg::CheckpointExists),
+            Self::PageNotInExport { page_id, path } => {
+                tr!(Msg::PageNotInExport, page_id, path.display())
+            }
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, NotionToObsidianError>;
@@ -0,0 +1,694 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use notion_client::objects::page::Page;
+use regex::Regex;
+use serde_json::{json, Value};
+use zip::{result::ZipResult, ZipArchive};
+
+use crate::{
+    blocks::compact_id,
+    converter::sanitize_filename,
+    error::{NotionToObsidianError, Result},
+    markdown::{fence_marker, is_fence_close},
+    traits::frontmatter_generator::yaml_string,
+    vault,
+};
+
+// Notionの「エクスポート → Markdown & CSV」のzip（APIを使わずに変換する）
+// ページは "タイトル <32桁のID>.md"、データベースは "名前 <ID>.csv" と行のページを置いたフォルダ "名前 <ID>/"
+pub struct NotionExport {
+    // zipのパス（エラーの表示に使う）
+    path: PathBuf,
+    pages: Vec<ExportedPage>,
+    // ページとデータベース以外のファイル（zip内のパス → 内容の場所）
+    files: HashMap<String, FileSource>,
+    archives: Archives,
+    // zip内のパス(.md) → ページのタイトル
+    titles: HashMap<String, String>,
+}
+
+// 添付ファイルはメモリに読み込まず、コピーするときにzipから読む
+enum FileSource {
+    Archive { archive: usize, index: usize },
+    Memory(Vec<u8>),
+}
+
+#[derive(Default)]
+struct Archives {
+    archives: Vec<Mutex<ZipArchive<File>>>,
+    // 分割されたzipを展開した一時ファイル（Dropで削除する）
+    temp_files: Vec<PathBuf>,
+}
+
+static TEMP_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedPage {
+    // ファイル名のIDをハイフン区切りにしたもの（IDがない場合はIDを除いたzip内のパス）
+    pub id: String,
+    pub title: String,
+    // zip内のパス
+    pub path: String,
+    // データベースの行の場合はCSVの列（タイトル以外、空の値は除く）
+    pub properties: Vec<(String, String)>,
+    // タイトルの見出しとプロパティの行を除いた本文
+    pub body: String,
+    // CSVの "Created"・"Last edited" 列（ない場合は不明）
+    pub created_time: Option<DateTime<Utc>>,
+    pub last_edited_time: Option<DateTime<Utc>>,
+}
+
+impl NotionExport {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut archives = Archives::default();
+        let mut entries = Vec::new();
+        archives
+            .open(path, &mut entries)
+            .map_err(|e| NotionToObsidianError::file_read(path, e))?;
+        Ok(Self::build(path, entries, archives))
+    }
+
+    // entriesは(zip内のパス, 内容)
+    pub fn from_entries(entries: Vec<(String, Vec<u8>)>) -> Self {
+        let entries = entries
+            .into_iter()
+            .map(|(path, content)| (path, FileSource::Memory(content)))
+            .collect();
+        Self::build(Path::new(""), entries, Archives::default())
+    }
+
+    // ページ(.md)とデータベース(.csv)はMemoryで渡す
+    fn build(path: &Path, entries: Vec<(String, FileSource)>, archives: Archives) -> Self {
+        let mut markdown = Vec::new();
+        let mut databases: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+        let mut files = HashMap::new();
+        for (path, source) in entries {
+            let content = match source {
+                FileSource::Memory(content) if path.ends_with(".md") || path.ends_with(".csv") => {
+                    content
+                }
+                source => {
+                    files.insert(path, source);
+                    continue;
+                }
+            };
+            if path.ends_with(".md") {
+                markdown.push((path, String::from_utf8_lossy(&content).into_owned()));
+            } else if let Some(stem) = path.strip_suffix(".csv") {
+                // _all.csvはビューの絞り込みによらずすべての行を含むため優先する
+                let (folder, all) = match stem.strip_suffix("_all") {
+                    Some(folder) => (folder, true),
+                    None => (stem, false),
+                };
+                if all || !databases.contains_key(folder) {
+                    let rows = parse_csv(&String::from_utf8_lossy(&content));
+                    databases.insert(folder.to_string(), rows);
+                }
+            }
+        }
+        markdown.sort();
+
+        // 同じタイトルの行が複数ある場合は順番に対応させる
+        let mut used_rows = HashSet::new();
+        let mut pages = Vec::new();
+        for (path, content) in markdown {
+            let name = file_name(&path).trim_end_matches(".md");
+            let (name, id) = split_id(name);
+            let (heading, body) = split_title(&content);
+            let title = heading.unwrap_or(name).to_string();
+
+            let folder = parent(&path);
+            let row = databases.get(folder).and_then(|rows| {
+                let (header, rows) = rows.split_first()?;
+                rows.iter()
+                    .enumerate()
+                    .find(|(index, row)| {
+                        row.first() == Some(&title)
+                            && used_rows.insert((folder.to_string(), *index))
+                    })
+                    .map(|(_, row)| (header, row))
+            });
+
+            let (properties, body) = match row {
+                Some((header, row)) => {
+                    let properties: Vec<(String, String)> = header
+                        .iter()
+                        .zip(row)
+                        .skip(1)
+                        .filter(|(_, value)| !value.is_empty())
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect();
+                    (properties, strip_properties(body, &header[1..]))
+                }
+                None => (Vec::new(), body.to_string()),
+            };
+
+            pages.push(ExportedPage {
+                id: id.map(hyphenate).unwrap_or_else(|| strip_ids(&path)),
+                title,
+                path,
+                created_time: csv_datetime(&properties, "Created"),
+                last_edited_time: csv_datetime(&properties, "Last edited"),
+                properties,
+                body,
+            });
+        }
+
+        let titles = pages
+            .iter()
+            .map(|page| (page.path.clone(), page.title.clone()))
+            .collect();
+        Self {
+            path: path.to_path_buf(),
+            pages,
+            files,
+            archives,
+            titles,
+        }
+    }
+
+    pub fn pages(&self) -> &[ExportedPage] {
+        &self.pages
+    }
+
+    pub fn page(&self, id: &str) -> Option<&ExportedPage> {
+        self.pages.iter().find(|page| page.id == id)
+    }
+
+    // pageと同じだが、見つからない場合はエラーにする
+    pub fn require_page(&self, id: &str) -> Result<&ExportedPage> {
+        self.page(id)
+            .ok_or_else(|| NotionToObsidianError::PageNotInExport {
+                page_id: id.to_string(),
+                path: self.path.clone(),
+            })
+    }
+
+    // 本文の相対リンクをWikiリンクに書き換え、コピーする添付ファイル（zip内のパス, バルト内の相対パス）を返す
+    // - ページ(.md)へのリンクはノートへのリンク
+    // - 添付ファイルはattachments_folder（未指定の場合はIDを除いたzip内のパス）にコピーする
+    // - データベース(.csv)へのリンクは対応するノートがないため文字列のみ残す
+    pub fn render(
+        &self,
+        page: &ExportedPage,
+        attachments_folder: Option<&str>,
+    ) -> (String, Vec<(String, String)>) {
+        let dir = parent(&page.path);
+        let mut attachments = Vec::new();
+
+        let body = replace_links(&page.body, |link| {
+            let (text, target) = (link.text, link.target);
+            if target.contains("://") || target.starts_with("mailto:") || target.starts_with('#') {
+                return None;
+            }
+            let path = resolve(dir, &percent_decode(target));
+
+            if let Some(title) = self.titles.get(&path) {
+                let note = sanitize_filename(title);
+                return Some(
+                    if text.is_empty() || text == title.as_str() || text == note {
+                        format!("[[{}]]", note)
+                    } else {
+                        format!("[[{}|{}]]", note, text)
+                    },
+                );
+            }
+            if path.ends_with(".csv") {
+                return Some(text.to_string());
+            }
+            if !self.files.contains_key(&path) {
+                return None;
+            }
+            // フォルダを指定した場合はダウンロードした添付ファイルと同じくファイル名で参照する
+            // 別のページの同じ名前のファイル（Untitled.pngなど）と重ならないようページのIDを付ける
+            let (target, link_target) = match attachments_folder {
+                Some(folder) => {
+                    let name = format!(
+                        "{}-{}",
+                        sanitize_filename(&compact_id(&page.id)),
+                        file_name(&path)
+                    );
+                    (format!("{}/{}", folder, name), name)
+                }
+                None => (strip_ids(&path), strip_ids(&path)),
+            };
+            attachments.push((path, target));
+            let embed = if link.embed { "!" } else { "" };
+            Some(format!("{}[[{}]]", embed, link_target))
+        });
+
+        (body, attachments)
+    }
+
+    pub fn copy_file(&self, path: &str, destination: &Path) -> Result<()> {
+        let source = self
+            .files
+            .get(path)
+            .ok_or_else(|| NotionToObsidianError::file_read(path, io::ErrorKind::NotFound))?;
+        if let Some(dir) = destination.parent() {
+            fs::create_dir_all(dir).map_err(|e| NotionToObsidianError::file_write(dir, e))?;
+        }
+        match source {
+            FileSource::Memory(content) => vault::write_atomic(destination, content),
+            FileSource::Archive { archive, index } => {
+                let mut archive = self.archives.archives[*archive].lock().unwrap();
+                let mut file = archive
+                    .by_index(*index)
+                    .map_err(|e| NotionToObsidianError::file_read(path, e))?;
+                vault::copy_atomic(destination, &mut file)
+            }
+        }
+    }
+}
+
+impl Archives {
+    // 大きなワークスペースのエクスポートはzipの中に分割したzipが入っている
+    // 分割したzipは一時ファイルに展開し、ページとデータベース以外は内容を読み込まずに場所だけ記録する
+    fn open(&mut self, path: &Path, entries: &mut Vec<(String, FileSource)>) -> ZipResult<()> {
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        let archive_index = self.archives.len();
+        let mut parts = Vec::new();
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index)?;
+            if file.is_dir() {
+                continue;
+            }
+            let name = file.name().replace('\\', "/");
+            if name.to_lowercase().ends_with(".zip") {
+                let count = TEMP_FILE_COUNT.fetch_add(1, Ordering::Relaxed);
+                let temp = std::env::temp_dir().join(format!(
+                    "notion-to-obsidian-{}-{}.zip",
+                    std::process::id(),
+                    count
+                ));
+                self.temp_files.push(temp.clone());
+                io::copy(&mut file, &mut File::create(&temp)?)?;
+                parts.push(temp);
+            } else if name.ends_with(".md") || name.ends_with(".csv") {
+                let mut content = Vec::new();
+                file.read_to_end(&mut content)?;
+                entries.push((name, FileSource::Memory(content)));
+            } else {
+                let archive = archive_index;
+                entries.push((name, FileSource::Archive { archive, index }));
+            }
+        }
+        self.archives.push(Mutex::new(archive));
+        for part in parts {
+            self.open(&part, entries)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Archives {
+    fn drop(&mut self) {
+        for path in &self.temp_files {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl ExportedPage {
+    // APIのページオブジェクトと同じ形にし、Frontmatterの生成や保存先の決定を共通化する
+    // エクスポートに含まれない作成・更新日時とURLは仮の値にし、remove_unknown_keysでFrontmatterから除く
+    pub fn to_page(&self) -> Result<Page> {
+        let time = |time: Option<DateTime<Utc>>| {
+            time.unwrap_or_default()
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+        };
+        let user = json!({ "object": "user", "id": "00000000-0000-0000-0000-000000000000" });
+        let url = if self.has_id() {
+            format!("https://www.notion.so/{}", compact_id(&self.id))
+        } else {
+            String::new()
+        };
+        let page = json!({
+            "object": "page",
+            "id": self.id,
+            "created_time": time(self.created_time),
+            "created_by": user,
+            "last_edited_time": time(self.last_edited_time),
+            "last_edited_by": user,
+            "cover": null,
+            "icon": null,
+            "parent": { "type": "workspace", "workspace": true },
+            "archived": false,
+            "in_trash": false,
+            "properties": {
+                "title": { "id": "title", "type": "title", "title": [rich_text(&self.title)] }
+            },
+            "url": url,
+            "public_url": null,
+        });
+        serde_json::from_value(page)
+            .map_err(|e| NotionToObsidianError::ConversionError(e.to_string()))
+    }
+
+    // ファイル名にNotionのIDを含むか（含まない場合はidがzip内のパス）
+    pub fn has_id(&self) -> bool {
+        split_id(file_name(&self.path).trim_end_matches(".md"))
+            .1
+            .is_some()
+    }
+
+    // to_pageの仮の値から生成したキー（created・updated・notion_id・notion_url）をFrontmatterから除く
+    pub fn remove_unknown_keys(&self, frontmatter: &str) -> String {
+        let unknown: Vec<&str> = [
+            ("created:", self.created_time.is_none()),
+            ("updated:", self.last_edited_time.is_none()),
+            ("notion_id:", !self.has_id()),
+            ("notion_url:", !self.has_id()),
+        ]
+        .into_iter()
+        .filter_map(|(key, unknown)| unknown.then_some(key))
+        .collect();
+        frontmatter
+            .split_inclusive('\n')
+            .filter(|line| !unknown.iter().any(|key| line.starts_with(key)))
+            .collect()
+    }
+}
+
+// CSVの日時の列（"March 5, 2024 3:04 PM" の形式で、エクスポートした環境のタイムゾーン）
+fn csv_datetime(properties: &[(String, String)], key: &str) -> Option<DateTime<Utc>> {
+    let value = &properties.iter().find(|(k, _)| k == key)?.1;
+    ["%B %d, %Y %I:%M %p", "%Y/%m/%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value.trim(), format).ok())
+        .and_then(|datetime| datetime.and_local_timezone(Local).single())
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+fn rich_text(content: &str) -> Value {
+    json!({
+        "type": "text",
+        "text": { "content": content, "link": null },
+        "annotations": {
+            "bold": false,
+            "italic": false,
+            "strikethrough": false,
+            "underline": false,
+            "code": false,
+            "color": "default"
+        },
+        "plain_text": content,
+        "href": null
+    })
+}
+
+// "ページ 1aeb266e0c708060a6fec6eb458e1379" → ("ページ", Some("1aeb266e0c708060a6fec6eb458e1379"))
+pub fn split_id(name: &str) -> (&str, Option<&str>) {
+    match name.rsplit_once(' ') {
+        Some((base, id)) if id.len() == 32 && id.chars().all(|c| c.is_ascii_hexdigit()) => {
+            (base, Some(id))
+        }
+        _ => (name, None),
+    }
+}
+
+// パスの各要素（フォルダ・ファイル名）からIDを除く（データベースの _all.csv の _all も除く）
+pub fn strip_ids(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if let (base, Some(_)) = split_id(segment) {
+                return base.to_string();
+            }
+            if let Some((stem, extension)) = segment.rsplit_once('.') {
+                let stem = stem.strip_suffix("_all").unwrap_or(stem);
+                if let (base, Some(_)) = split_id(stem) {
+                    return format!("{}.{}", base, extension);
+                }
+            }
+            segment.to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn hyphenate(id: &str) -> String {
+    format!(
+        "{}-{}-{}-{}-{}",
+        &id[0..8],
+        &id[8..12],
+        &id[12..16],
+        &id[16..20],
+        &id[20..]
+    )
+}
+
+// RFC 4180のCSV（引用符内の改行・カンマ・"" に対応する）
+pub fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let content = content.trim_start_matches('\u{feff}');
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|field| !field.is_empty()));
+    rows
+}
+
+// 先頭の "# タイトル" とそれ以降の本文
+fn split_title(content: &str) -> (Option<&str>, &str) {
+    let content = content.trim_start_matches('\u{feff}').trim_start();
+    match content.strip_prefix("# ") {
+        Some(rest) => {
+            let (title, body) = rest.split_once('\n').unwrap_or((rest, ""));
+            (Some(title.trim()), body)
+        }
+        None => (None, content),
+    }
+}
+
+// タイトルの後に "プロパティ名: 値" の形で出力されるプロパティの行を除く
+fn strip_properties(body: &str, keys: &[String]) -> String {
+    let lines: Vec<&str> = body.lines().collect();
+    let start = lines
+        .iter()
+        .position(|line| !line.trim().is_empty())
+        .unwrap_or(lines.len());
+    let end = lines[start..]
+        .iter()
+        .position(|line| {
+            !line
+                .split_once(':')
+                .is_some_and(|(key, _)| keys.iter().any(|k| k == key))
+        })
+        .map_or(lines.len(), |offset| start + offset);
+    lines[end..].join("\n")
+}
+
+// Frontmatterの閉じる --- の前にCSVの列を追加する（生成済みのキーと同じ列は追加しない）
+pub fn insert_properties(frontmatter: &str, properties: &[(String, String)]) -> String {
+    let lines: String = properties
+        .iter()
+        .filter(|(key, _)| {
+            !frontmatter.lines().any(|line| {
+                line.strip_prefix(key.as_str())
+                    .is_some_and(|rest| rest.starts_with(':'))
+            })
+        })
+        .map(|(key, value)| property_yaml(key, value))
+        .collect();
+    if lines.is_empty() {
+        return frontmatter.to_string();
+    }
+    match frontmatter.strip_suffix("---\n") {
+        Some(head) if head.starts_with("---\n") => format!("{}{}---\n", head, lines),
+        _ => format!("---\n{}---\n{}", lines, frontmatter),
+    }
+}
+
+// リレーションの列（"ページ (ページ%20<ID>.md), ..."）はWikiリンクのリストにする
+fn property_yaml(key: &str, value: &str) -> String {
+    let key = if key
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '_' | '-'))
+    {
+        key.to_string()
+    } else {
+        yaml_string(key)
+    };
+    match relation_titles(value) {
+        Some(titles) => {
+            let items: String = titles
+                .iter()
+                .map(|title| format!("  - {}\n", yaml_string(&format!("[[{}]]", title))))
+                .collect();
+            format!("{}:\n{}", key, items)
+        }
+        None => format!("{}: {}\n", key, yaml_string(value)),
+    }
+}
+
+fn relation_titles(value: &str) -> Option<Vec<String>> {
+    let pattern = Regex::new(r"^(?:[^,]+? \([^()\s]+\)(?:, |$))+$").unwrap();
+    if !pattern.is_match(value) {
+        return None;
+    }
+    let item = Regex::new(r"([^,]+?) \(([^()\s]+)\)").unwrap();
+    let titles = item
+        .captures_iter(value)
+        .filter(|caps| caps[2].ends_with(".md") || caps[2].contains("notion.so"))
+        .map(|caps| sanitize_filename(caps[1].trim()))
+        .collect::<Vec<_>>();
+    (!titles.is_empty()).then_some(titles)
+}
+
+struct Link<'a> {
+    embed: bool,
+    text: &'a str,
+    target: &'a str,
+}
+
+// [テキスト](リンク先) と ![テキスト](リンク先) をreplaceの結果に置き換える（Noneの場合はそのまま）
+// リンク先の括弧は対応する閉じ括弧までを含め、コードブロックの中は置き換えない
+fn replace_links(body: &str, mut replace: impl FnMut(&Link) -> Option<String>) -> String {
+    let mut fence: Option<&str> = None;
+    let mut lines = Vec::new();
+    for line in body.split('\n') {
+        match fence {
+            Some(marker) => {
+                if is_fence_close(line, marker) {
+                    fence = None;
+                }
+                lines.push(line.to_string());
+                continue;
+            }
+            None => {
+                if let Some(marker) = fence_marker(line) {
+                    fence = Some(marker);
+                    lines.push(line.to_string());
+                    continue;
+                }
+            }
+        }
+
+        let mut output = String::new();
+        let mut rest = line;
+        while let Some(open) = rest.find('[') {
+            let link = rest[open + 1..].find(']').and_then(|close| {
+                let text = &rest[open + 1..open + 1 + close];
+                let after = &rest[open + close + 2..];
+                let length = after.strip_prefix('(').and_then(link_target_length)?;
+                Some((text, &after[1..1 + length], open + close + length + 4))
+            });
+            let Some((text, target, end)) = link else {
+                output.push_str(&rest[..open + 1]);
+                rest = &rest[open + 1..];
+                continue;
+            };
+            let embed = rest[..open].ends_with('!');
+            let start = if embed { open - 1 } else { open };
+            output.push_str(&rest[..start]);
+            let link = Link {
+                embed,
+                text,
+                target,
+            };
+            match replace(&link) {
+                Some(replaced) => output.push_str(&replaced),
+                None => output.push_str(&rest[start..end]),
+            }
+            rest = &rest[end..];
+        }
+        output.push_str(rest);
+        lines.push(output);
+    }
+    lines.join("\n")
+}
+
+// 対応する閉じ括弧までの長さ（空白を含む場合はリンクではない）
+fn link_target_length(value: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return (i > 0).then_some(i),
+            ')' => depth -= 1,
+            c if c.is_whitespace() => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit_once('/').map_or(path, |(_, name)| name)
+}
+
+// dirからの相対パスをzip内のパスにする
+fn resolve(dir: &str, target: &str) -> String {
+    let mut segments: Vec<&str> = dir.split('/').filter(|s| !s.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
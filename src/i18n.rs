@@ -0,0 +1,393 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+// エラーとCLIのメッセージの言語
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    En,
+    Ja,
+}
+
+impl Language {
+    // "ja"・"ja_JP.UTF-8"・"en-US" などのロケール名
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        if value.starts_with("ja") {
+            Some(Language::Ja)
+        } else if value.starts_with("en") {
+            Some(Language::En)
+        } else {
+            None
+        }
+    }
+
+    // MESSAGE_LANGUAGE、システムのロケール（LC_ALL・LC_MESSAGES・LANG）の順に参照し、どちらでもなければ英語
+    pub fn detect() -> Self {
+        ["MESSAGE_LANGUAGE", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find_map(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+}
+
+// 0: 未設定（最初に参照したときに検出する）
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_language(language: Language) {
+    let value = match language {
+        Language::En => 1,
+        Language::Ja => 2,
+    };
+    LANGUAGE.store(value, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::En,
+        2 => Language::Ja,
+        _ => {
+            let language = Language::detect();
+            set_language(language);
+            language
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    // エラー
+    NoToken,
+    NotionApi,
+    BlockRetrieval,
+    PageRetrieval,
+    DatabaseQuery,
+    CommentRetrieval,
+    PageUpdate,
+    ParentPageNotFound,
+    Conversion,
+    Download,
+    FileWrite,
+    FileRead,
+    PostProcess,
+    EnvVar,
+    NoCheckpoint,
+    CheckpointExists,
+    PageNotInExport,
+    WriteInterrupted,
+    // CLI
+    RequiredEnvVar,
+    SetRequiredEnvVars,
+    Usage,
+    UsagePage,
+    UsageLimit,
+    UsageResume,
+    UsageTasks,
+    MissingPageId,
+    MissingLimit,
+    MissingTaskArgs,
+    InvalidArgument,
+    TasksExported,
+    CancelRequested,
+    PageFailed,
+    PostProcessFailed,
+    Finished,
+    Cancelled,
+    PartiallyMigrated,
+    RetrySucceeded,
+    RetryFailed,
+    LoadTagsFailed,
+    LoadRelationCacheFailed,
+    // doctor
+    UsageDoctor,
+    DoctorToken,
+    DoctorTokenOk,
+    DoctorTokenFix,
+    DoctorUserCapabilityFix,
+    DoctorEnvVarMissing,
+    DoctorRetryFix,
+    DoctorSkipped,
+    DoctorDatabase,
+    DoctorDatabaseOk,
+    DoctorDatabaseIdFix,
+    DoctorShareFix,
+    DoctorProperty,
+    DoctorPropertyMissing,
+    DoctorPropertyWrongType,
+    DoctorPropertyAddFix,
+    DoctorPropertyTypeFix,
+    DoctorOutputDir,
+    DoctorOutputDirMissing,
+    DoctorOutputDirNotDir,
+    DoctorOutputDirFix,
+    DoctorPermissionFix,
+    DoctorSummaryOk,
+    DoctorSummaryProblems,
+    // export
+    UsageExport,
+    MissingExportPath,
+    // ログ
+    CommentsUnavailable,
+    NoteUrlSkipped,
+    CheckpointSaveFailed,
+    JournalUpdateFailed,
+    BlockFetchFailed,
+    LinkedPageFetchFailed,
+    FrontmatterFailed,
+    FrontmatterFinishFailed,
+    FileDownloadFailed,
+    RelationPageFetchFailed,
+    RelationFetchFailed,
+    TagReloadFailed,
+    RelationPageNoTitle,
+    PostProcessStepDone,
+    PostProcessStepFailed,
+}
+
+impl Msg {
+    // {} は引数に順番に置き換える
+    pub fn template(self, language: Language) -> &'static str {
+        use Language::*;
+        use Msg::*;
+        match (self, language) {
+            (NoToken, En) => "Notion API token is not set",
+            (NoToken, Ja) => "APIトークンが設定されていません",
+            (NotionApi, En) => "Notion API request failed: {}",
+            (NotionApi, Ja) => "Notion APIの呼び出しに失敗しました: {}",
+            (BlockRetrieval, En) => "Failed to retrieve Notion block {}: {}",
+            (BlockRetrieval, Ja) => "Notionのブロック {} の取得に失敗しました: {}",
+            (PageRetrieval, En) => "Failed to retrieve Notion page {}: {}",
+            (PageRetrieval, Ja) => "Notionのページ {} の取得に失敗しました: {}",
+            (DatabaseQuery, En) => "Failed to query Notion database {}: {}",
+            (DatabaseQuery, Ja) => "Notionのデータベース {} の取得に失敗しました: {}",
+            (CommentRetrieval, En) => "Failed to retrieve comments on {}: {}",
+            (CommentRetrieval, Ja) => "{} のコメントの取得に失敗しました: {}",
+            (PageUpdate, En) => "Failed to update Notion page {}: {}",
+            (PageUpdate, Ja) => "Notionのページ {} の更新に失敗しました: {}",
+            (ParentPageNotFound, En) => "Parent page of block {} was not found",
+            (ParentPageNotFound, Ja) => "ブロック {} の親ページが見つかりません",
+            (Conversion, En) => "Conversion failed: {}",
+            (Conversion, Ja) => "変換処理に失敗しました: {}",
+            (Download, En) => "Failed to download {}: {}",
+            (Download, Ja) => "{} のダウンロードに失敗しました: {}",
+            (FileWrite, En) => "Failed to write {}: {}",
+            (FileWrite, Ja) => "{} の書き込みに失敗しました: {}",
+            (FileRead, En) => "Failed to read {}: {}",
+            (FileRead, Ja) => "{} の読み込みに失敗しました: {}",
+            (PostProcess, En) => "Post-processing {} failed: {}",
+            (PostProcess, Ja) => "後処理 {} に失敗しました: {}",
+            (EnvVar, En) => "Environment variable is not set: {}",
+            (EnvVar, Ja) => "環境変数が設定されていません: {}",
+            (NoCheckpoint, En) => "There is no interrupted run to resume",
+            (NoCheckpoint, Ja) => "再開できる変換がありません",
+            (CheckpointExists, En) => {
+                "A previous run was interrupted. Run with `--resume` to convert the remaining pages, or delete .notion-to-obsidian/checkpoint.json to start over"
+            }
+            (CheckpointExists, Ja) => {
+                "中断した変換が残っています。`--resume`で残りのページを変換するか、.notion-to-obsidian/checkpoint.jsonを削除してやり直してください"
+            }
+            (PageNotInExport, En) => "Page {} was not found in the export {}",
+            (PageNotInExport, Ja) => "ページ {} がエクスポート {} にありません",
+            (WriteInterrupted, En) => "Writing the note was interrupted in the previous run",
+            (WriteInterrupted, Ja) => "前回の実行でノートの書き込みが中断されました",
+            (RequiredEnvVar, En) => "Error: {} is not set in environment variables",
+            (RequiredEnvVar, Ja) => "エラー: 環境変数{}が設定されていません",
+            (SetRequiredEnvVars, En) => {
+                "Please set all required environment variables in .env file"
+            }
+            (SetRequiredEnvVars, Ja) => ".envファイルで必要な環境変数をすべて設定してください",
+            (Usage, En) => "Usage:",
+            (Usage, Ja) => "使用方法:",
+            (UsagePage, En) => "  {} --page <page_id> : convert a single page",
+            (UsagePage, Ja) => "  {} --page <page_id> : 特定のページを変換",
+            (UsageLimit, En) => "  {} --limit <number> : convert up to <number> pages",
+            (UsageLimit, Ja) => "  {} --limit <number> : 指定数のページを変換",
+            (UsageResume, En) => "  {} --resume : resume an interrupted run",
+            (UsageResume, Ja) => "  {} --resume : 中断した変換を再開",
+            (UsageTasks, En) => {
+                "  {} --tasks <database_id> <note_title> : export a task database to a note"
+            }
+            (UsageTasks, Ja) => {
+                "  {} --tasks <database_id> <note_title> : タスクデータベースをノートに書き出し"
+            }
+            (MissingPageId, En) => "Please specify a page ID",
+            (MissingPageId, Ja) => "ページIDを指定してください",
+            (MissingLimit, En) => "Please specify the number of pages to convert",
+            (MissingLimit, Ja) => "変換するページ数を指定してください",
+            (MissingTaskArgs, En) => "Please specify the task database ID and the note title",
+            (MissingTaskArgs, Ja) => "タスクデータベースのIDとノート名を指定してください",
+            (InvalidArgument, En) => "Invalid argument",
+            (InvalidArgument, Ja) => "不正な引数です",
+            (TasksExported, En) => "Done: exported {} tasks",
+            (TasksExported, Ja) => "変換完了: {} 件のタスクを書き出しました",
+            (CancelRequested, En) => {
+                "Stopping after the current page (press again to exit immediately)"
+            }
+            (CancelRequested, Ja) => {
+                "処理中のページを変換してから中止します（もう一度押すと即座に終了します）"
+            }
+            (PageFailed, En) => "Failed to convert page: {} ({})",
+            (PageFailed, Ja) => "ページの変換に失敗: {} ({})",
+            (PostProcessFailed, En) => "Post-processing failed: {} ({})",
+            (PostProcessFailed, Ja) => "後処理に失敗: {} ({})",
+            (Finished, En) => "Done: converted {} / {} pages",
+            (Finished, Ja) => "変換完了: {} / {} ページを変換しました",
+            (Cancelled, En) => "Cancelled. Run with `--resume` to convert the remaining pages",
+            (Cancelled, Ja) => "中止しました。`--resume`で残りのページの変換を再開できます",
+            (PartiallyMigrated, En) => {
+                "{} pages were saved but post-processing failed (will retry on the next run)"
+            }
+            (PartiallyMigrated, Ja) => {
+                "{} ページは保存しましたが後処理に失敗しました（次回の実行で再試行します）"
+            }
+            (RetrySucceeded, En) => "Retried post-processing: {}",
+            (RetrySucceeded, Ja) => "後処理を再試行しました: {}",
+            (RetryFailed, En) => "Retrying post-processing failed: {} ({})",
+            (RetryFailed, Ja) => "後処理の再試行に失敗: {} ({})",
+            (LoadTagsFailed, En) => "Failed to load tags",
+            (LoadTagsFailed, Ja) => "タグの読み込みに失敗しました",
+            (LoadRelationCacheFailed, En) => "Failed to load the relation cache",
+            (LoadRelationCacheFailed, Ja) => "リレーションのキャッシュの読み込みに失敗しました",
+            (UsageDoctor, En) => "  {} --doctor : check the token, sharing and settings",
+            (UsageDoctor, Ja) => "  {} --doctor : トークン・共有設定・プロパティを診断",
+            (DoctorToken, En) => "Notion token",
+            (DoctorToken, Ja) => "Notionのトークン",
+            (DoctorTokenOk, En) => "Authenticated",
+            (DoctorTokenOk, Ja) => "認証できました",
+            (DoctorTokenFix, En) => {
+                "Copy the internal integration secret from https://www.notion.so/my-integrations into NOTION_TOKEN"
+            }
+            (DoctorTokenFix, Ja) => {
+                "https://www.notion.so/my-integrations でインテグレーションのシークレットをコピーしてNOTION_TOKENに設定してください"
+            }
+            (DoctorUserCapabilityFix, En) => {
+                "Enable \"Read user information\" in the integration capabilities to link people (LINK_PEOPLE)"
+            }
+            (DoctorUserCapabilityFix, Ja) => {
+                "ユーザーをリンクする（LINK_PEOPLE）場合はインテグレーションの機能で「ユーザー情報の読み取り」を有効にしてください"
+            }
+            (DoctorEnvVarMissing, En) => "{} is not set",
+            (DoctorEnvVarMissing, Ja) => "{}が設定されていません",
+            (DoctorRetryFix, En) => "This looks temporary. Wait a moment and run --doctor again",
+            (DoctorRetryFix, Ja) => "一時的なエラーです。しばらく待ってから再度--doctorを実行してください",
+            (DoctorSkipped, En) => "Skipped because the token could not be verified",
+            (DoctorSkipped, Ja) => "トークンを確認できなかったため確認していません",
+            (DoctorDatabase, En) => "Database {}",
+            (DoctorDatabase, Ja) => "データベース {}",
+            (DoctorDatabaseOk, En) => "Accessible ({})",
+            (DoctorDatabaseOk, Ja) => "アクセスできます（{}）",
+            (DoctorDatabaseIdFix, En) => {
+                "Set {} to the database ID (the 32 characters before ?v= in the database URL)"
+            }
+            (DoctorDatabaseIdFix, Ja) => {
+                "{}にデータベースのID（データベースのURLの?v=の前の32文字）を設定してください"
+            }
+            (DoctorShareFix, En) => {
+                "Open the database in Notion, choose ••• → Connections and add your integration. Also check that the ID points to a database, not a page"
+            }
+            (DoctorShareFix, Ja) => {
+                "Notionでデータベースを開き、「•••」→「コネクト」からインテグレーションを追加してください。IDがページではなくデータベースのものかも確認してください"
+            }
+            (DoctorProperty, En) => "Property {} in {}",
+            (DoctorProperty, Ja) => "プロパティ {}（{}）",
+            (DoctorPropertyMissing, En) => "Not found",
+            (DoctorPropertyMissing, Ja) => "見つかりません",
+            (DoctorPropertyWrongType, En) => "Type is {} (expected {})",
+            (DoctorPropertyWrongType, Ja) => "種類が{}です（{}である必要があります）",
+            (DoctorPropertyAddFix, En) => "Add a {} property named {} to the database",
+            (DoctorPropertyAddFix, Ja) => "データベースに{}プロパティ「{}」を追加してください",
+            (DoctorPropertyTypeFix, En) => "Change the type of {} to {}",
+            (DoctorPropertyTypeFix, Ja) => "「{}」の種類を{}に変更してください",
+            (DoctorOutputDir, En) => "Output directory",
+            (DoctorOutputDir, Ja) => "出力先のフォルダ",
+            (DoctorOutputDirMissing, En) => "{} does not exist",
+            (DoctorOutputDirMissing, Ja) => "{} が存在しません",
+            (DoctorOutputDirNotDir, En) => "{} is not a directory",
+            (DoctorOutputDirNotDir, Ja) => "{} はフォルダではありません",
+            (DoctorOutputDirFix, En) => "Set OBSIDIAN_DIR to an existing folder in your vault",
+            (DoctorOutputDirFix, Ja) => "OBSIDIAN_DIRにバルト内の既存のフォルダを設定してください",
+            (DoctorPermissionFix, En) => "Allow the current user to write to {}, or choose another folder",
+            (DoctorPermissionFix, Ja) => "{} に書き込み権限を付与するか、別のフォルダを指定してください",
+            (DoctorSummaryOk, En) => "No problems found",
+            (DoctorSummaryOk, Ja) => "問題は見つかりませんでした",
+            (DoctorSummaryProblems, En) => "{} errors, {} warnings",
+            (DoctorSummaryProblems, Ja) => "エラー {} 件、警告 {} 件",
+            (UsageExport, En) => {
+                "  {} --export <zip> [--resume] : convert a Notion \"Markdown & CSV\" export without the API"
+            }
+            (UsageExport, Ja) => {
+                "  {} --export <zip> [--resume] : Notionのエクスポート（Markdown & CSV）をAPIを使わずに変換"
+            }
+            (MissingExportPath, En) => "Please specify the exported zip file",
+            (MissingExportPath, Ja) => "エクスポートしたzipファイルを指定してください",
+            (CommentsUnavailable, En) => {
+                "Skipping comments on {} (enable \"Read comments\" in the integration capabilities): {}"
+            }
+            (CommentsUnavailable, Ja) => {
+                "{} のコメントを出力しません（インテグレーションの機能で「コメントの読み取り」を有効にしてください）: {}"
+            }
+            (NoteUrlSkipped, En) => "Skipping the note URL of {} because no note was saved",
+            (NoteUrlSkipped, Ja) => "{} はノートを保存していないため、ノートのURLを書き込みません",
+            (CheckpointSaveFailed, En) => "Failed to save the checkpoint: {}",
+            (CheckpointSaveFailed, Ja) => "チェックポイントの保存に失敗: {}",
+            (JournalUpdateFailed, En) => "Failed to update the journal: {}",
+            (JournalUpdateFailed, Ja) => "ジャーナルの更新に失敗: {}",
+            (BlockFetchFailed, En) => "Failed to retrieve block {} ({})",
+            (BlockFetchFailed, Ja) => "ブロックの取得に失敗: {} ({})",
+            (LinkedPageFetchFailed, En) => "Failed to retrieve linked page {} ({})",
+            (LinkedPageFetchFailed, Ja) => "リンク先のページの取得に失敗: {} ({})",
+            (FrontmatterFailed, En) => "Failed to generate frontmatter: {}",
+            (FrontmatterFailed, Ja) => "Frontmatterの生成に失敗: {}",
+            (FrontmatterFinishFailed, En) => "Failed to finish generating frontmatter: {}",
+            (FrontmatterFinishFailed, Ja) => "Frontmatterの後処理に失敗: {}",
+            (FileDownloadFailed, En) => "Failed to download {} ({})",
+            (FileDownloadFailed, Ja) => "ファイルのダウンロードに失敗: {} ({})",
+            (RelationPageFetchFailed, En) => "Failed to retrieve related page {} ({})",
+            (RelationPageFetchFailed, Ja) => "リレーション先のページの取得に失敗: {} ({})",
+            (RelationFetchFailed, En) => "Failed to retrieve relations of {} ({})",
+            (RelationFetchFailed, Ja) => "リレーションの取得に失敗: {} ({})",
+            (TagReloadFailed, En) => "Failed to reload tags: {}",
+            (TagReloadFailed, Ja) => "タグの再読み込みに失敗: {}",
+            (RelationPageNoTitle, En) => "Related page {} has no title",
+            (RelationPageNoTitle, Ja) => "リレーション先のページにタイトルがありません: {}",
+            (PostProcessStepDone, En) => "Post-processing {} finished: {}",
+            (PostProcessStepDone, Ja) => "後処理 {} が完了: {}",
+            (PostProcessStepFailed, En) => "Post-processing {} failed: {} ({})",
+            (PostProcessStepFailed, Ja) => "後処理 {} に失敗: {} ({})",
+        }
+    }
+}
+
+// 現在の言語のメッセージを返す（tr!マクロから使う）
+pub fn format(msg: Msg, args: &[&dyn Display]) -> String {
+    // 引数の過不足はメッセージが欠けるだけで気づきにくいため、デバッグビルドでは確認する
+    debug_assert!(
+        [Language::En, Language::Ja].iter().all(|&language| msg
+            .template(language)
+            .matches("{}")
+            .count()
+            == args.len()),
+        "wrong number of arguments for {:?}",
+        msg
+    );
+    let mut parts = msg.template(language()).split("{}");
+    let mut message = parts.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            message.push_str(&arg.to_string());
+        }
+        message.push_str(part);
+    }
+    message
+}
+
+#[macro_export]
+macro_rules! tr {
+    ($msg:expr $(, $arg:expr)* $(,)?) => {
+        $crate::i18n::format($msg, &[$(&$arg as &dyn ::std::fmt::Display),*])
+    };
+}
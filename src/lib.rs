@@ -1,8 +1,27 @@
+pub mod anchors;
+mod api;
+pub mod blocks;
 pub mod builder;
+pub mod columns;
+pub mod comments;
 pub mod converter;
+pub mod dates;
+pub mod doctor;
 pub mod error;
+pub mod export;
+pub mod i18n;
+pub mod markdown;
+pub mod media;
+pub mod migration;
+pub mod properties;
+pub mod relations;
+pub mod renderer;
+pub mod synced;
+pub mod tasks;
 pub mod traits;
+pub mod users;
+pub mod vault;
 
 pub use error::{NotionToObsidianError, Result};
 
-pub use converter::NotionToObsidian;
+pub use converter::{ConvertOptions, NotionToObsidian};
@@ -1,16 +1,76 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
 use anyhow::Context;
 use dotenv::dotenv;
 use notion_to_obsidian_rs::{
     builder::NotionToObsidianBuilder,
+    columns::ColumnLayout,
+    comments::CommentsMode,
+    dates::DateStyle,
+    doctor::{CheckStatus, Doctor, PropertyRequirement},
+    export::NotionExport,
+    i18n::{self, Language, Msg},
+    media::{EmbedFallback, MediaOptions},
+    migration::{CancellationToken, MigrationReport, PageOutcome},
+    properties,
+    relations::{PageTitleCache, RelationResolver},
+    synced::SyncedBlockMode,
+    tasks::TaskDatabase,
+    tr,
     traits::{
-        post_processor::MyPostProcessor, DatabasePageProvider, MyFrontmatterGenerator,
-        SinglePageProvider,
+        post_processor::{
+            AppendComment, ArchivePage, OptionPropertyKind, SetCheckbox, SetOption, WriteNoteUrl,
+        },
+        DatabasePageProvider, DefaultFrontmatterGenerator, MigrationEvent, MigrationObserver,
+        MyFrontmatterGenerator, PostProcessorChain, SinglePageProvider,
     },
+    users::UserDirectory,
+    NotionToObsidianError,
 };
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() {
     dotenv().ok();
+    // MESSAGE_LANGUAGE: en / ja（未指定の場合はシステムのロケール、どちらでもなければ英語）
+    i18n::set_language(Language::detect());
+
+    if let Err(e) = run().await {
+        // スクリプトから判別できるようにエラーコードを付ける
+        match e.downcast_ref::<NotionToObsidianError>() {
+            Some(error) => eprintln!("error[{}]: {}", error.code(), error_message(&e)),
+            None => eprintln!("error: {}", error_message(&e)),
+        }
+        std::process::exit(1);
+    }
+}
+
+// NotionToObsidianErrorのメッセージは元のエラーを含むため、それより先の原因は表示しない
+fn error_message(e: &anyhow::Error) -> String {
+    let mut messages = Vec::new();
+    for cause in e.chain() {
+        messages.push(cause.to_string());
+        if cause.is::<NotionToObsidianError>() {
+            break;
+        }
+    }
+    messages.join(": ")
+}
+
+async fn run() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    // 診断は環境変数が揃っていなくても実行し、足りないものを報告する
+    if args.get(1).map(String::as_str) == Some("--doctor") {
+        let ok = doctor().await;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+    // エクスポートのzipからの変換はAPIを使わないため、OBSIDIAN_DIRのみ必要
+    if args.get(1).map(String::as_str) == Some("--export") {
+        return export(&args).await;
+    }
 
     let required_vars = [
         "NOTION_TOKEN",
@@ -20,18 +80,19 @@ async fn main() -> anyhow::Result<()> {
     ];
     for var in required_vars {
         if std::env::var(var).is_err() {
-            eprintln!("Error: {} is not set in environment variables", var);
-            eprintln!("Please set all required environment variables in .env file");
+            eprintln!("{}", tr!(Msg::RequiredEnvVar, var));
+            eprintln!("{}", tr!(Msg::SetRequiredEnvVars));
             std::process::exit(1);
         }
     }
 
-    let token = std::env::var("NOTION_TOKEN").context("NOTION_TOKENが設定されていません")?;
-    let obsidian_dir = std::env::var("OBSIDIAN_DIR").context("OBSIDIAN_DIRが設定されていません")?;
-    let tag_database_id =
-        std::env::var("TAG_DATABASE_ID").context("TAG_DATABASE_IDが設定されていません")?;
-    let database_id =
-        std::env::var("ALL_DATABASE_ID").context("ALL_DATABASE_IDが設定されていません")?;
+    let env = |name: &str| {
+        std::env::var(name).map_err(|_| NotionToObsidianError::EnvVarError(name.to_string()))
+    };
+    let token = env("NOTION_TOKEN")?;
+    let obsidian_dir = env("OBSIDIAN_DIR")?;
+    let tag_database_id = env("TAG_DATABASE_ID")?;
+    let database_id = env("ALL_DATABASE_ID")?;
 
     // let mut converter = NotionToObsidian::new(
     //     token,
@@ -41,61 +102,462 @@ async fn main() -> anyhow::Result<()> {
     // タグデータの読み込み
     // converter.load_tags(&tag_database_id).await?;
 
-    let args: Vec<String> = std::env::args().collect();
+    // LINK_PEOPLE=true の場合のみユーザー一覧を読み込む
+    let user_directory = if std::env::var("LINK_PEOPLE").as_deref() == Ok("true") {
+        let mut users = UserDirectory::new(token.clone()).await?;
+        if let Ok(folder) = std::env::var("PEOPLE_DIR") {
+            users = users.with_stub_folder(folder);
+        }
+        Some(Arc::new(users))
+    } else {
+        None
+    };
+
     if args.len() < 2 {
-        eprintln!("使用方法:");
-        eprintln!("  {} --page <page_id> : 特定のページを変換", args[0]);
-        eprintln!("  {} --limit <number> : 指定数のページを変換", args[0]);
+        eprintln!("{}", tr!(Msg::Usage));
+        for usage in [
+            Msg::UsagePage,
+            Msg::UsageLimit,
+            Msg::UsageResume,
+            Msg::UsageTasks,
+            Msg::UsageDoctor,
+            Msg::UsageExport,
+        ] {
+            eprintln!("{}", tr!(usage, args[0]));
+        }
         std::process::exit(1);
     }
 
     match args[1].as_str() {
         "--page" => {
             if args.len() < 3 {
-                eprintln!("ページIDを指定してください");
+                eprintln!("{}", tr!(Msg::MissingPageId));
                 std::process::exit(1);
             }
             let page_id = &args[2];
 
+            let post_processor = post_processor_chain(&obsidian_dir);
             let converter = NotionToObsidianBuilder::new(token.clone())
                 .with_output_path(obsidian_dir)
                 .with_page_provider(Box::new(SinglePageProvider::new(page_id.to_string())))
+                .with_observer(Box::new(ProgressBar::default()))
                 .with_frontmatter_generator(Box::new(
-                    MyFrontmatterGenerator::new(&tag_database_id, token).await,
+                    frontmatter_generator(&tag_database_id, token, &user_directory).await?,
                 ))
-                .with_post_processor(Box::new(MyPostProcessor {}))
-                .build()?;
+                .with_post_processor(Box::new(post_processor));
+            let converter = apply_env_options(converter, user_directory).build()?;
 
-            converter.migrate_pages().await?;
-            println!("変換完了: ページを変換しました");
+            let report = converter.migrate_pages(&cancel_on_ctrl_c()).await?;
+            print_report(&report);
         }
         "--limit" => {
             if args.len() < 3 {
-                eprintln!("変換するページ数を指定してください");
+                eprintln!("{}", tr!(Msg::MissingLimit));
                 std::process::exit(1);
             }
             let limit = args[2].parse::<usize>().unwrap_or(5);
 
+            let post_processor = post_processor_chain(&obsidian_dir);
             let converter = NotionToObsidianBuilder::new(token.clone())
                 .with_output_path(obsidian_dir)
                 .with_page_provider(Box::new(DatabasePageProvider::new(database_id, limit)))
+                .with_observer(Box::new(ProgressBar::default()))
+                .with_frontmatter_generator(Box::new(
+                    frontmatter_generator(&tag_database_id, token, &user_directory).await?,
+                ))
+                .with_post_processor(Box::new(post_processor));
+            let converter = apply_env_options(converter, user_directory).build()?;
+
+            let report = converter.migrate_pages(&cancel_on_ctrl_c()).await?;
+            print_report(&report);
+        }
+        "--resume" => {
+            let post_processor = post_processor_chain(&obsidian_dir);
+            let converter = NotionToObsidianBuilder::new(token.clone())
+                .with_output_path(obsidian_dir)
+                .with_observer(Box::new(ProgressBar::default()))
                 .with_frontmatter_generator(Box::new(
-                    MyFrontmatterGenerator::new(&tag_database_id, token).await,
+                    frontmatter_generator(&tag_database_id, token, &user_directory).await?,
                 ))
-                .with_post_processor(Box::new(MyPostProcessor {}))
-                .build()?;
+                .with_post_processor(Box::new(post_processor));
+            let converter = apply_env_options(converter, user_directory).build()?;
+
+            let report = converter.resume_pages(&cancel_on_ctrl_c()).await?;
+            print_report(&report);
+        }
+        "--tasks" => {
+            if args.len() < 4 {
+                eprintln!("{}", tr!(Msg::MissingTaskArgs));
+                std::process::exit(1);
+            }
+            let task_database = TaskDatabase::new(args[2].clone(), args[3].clone());
+
+            let converter = NotionToObsidianBuilder::new(token).with_output_path(obsidian_dir);
+            let converter = apply_env_options(converter, user_directory).build()?;
 
-            let (success_count, total_count) = converter.migrate_pages().await?;
-            println!(
-                "変換完了: {} / {} ページを変換しました",
-                success_count, total_count
-            );
+            let count = converter.export_task_database(&task_database).await?;
+            println!("{}", tr!(Msg::TasksExported, count));
         }
         _ => {
-            eprintln!("不正な引数です");
+            eprintln!("{}", tr!(Msg::InvalidArgument));
             std::process::exit(1);
         }
     }
 
     Ok(())
 }
+
+// --export <zip> [--resume]
+async fn export(args: &[String]) -> anyhow::Result<()> {
+    let Some(zip) = args.get(2) else {
+        eprintln!("{}", tr!(Msg::MissingExportPath));
+        std::process::exit(1);
+    };
+    let obsidian_dir = std::env::var("OBSIDIAN_DIR")
+        .map_err(|_| NotionToObsidianError::EnvVarError("OBSIDIAN_DIR".to_string()))?;
+    let export = NotionExport::open(Path::new(zip))?;
+
+    let converter = NotionToObsidianBuilder::new(String::new())
+        .with_output_path(obsidian_dir)
+        .with_export(Arc::new(export))
+        .with_observer(Box::new(ProgressBar::default()))
+        .with_frontmatter_generator(Box::new(DefaultFrontmatterGenerator::default()));
+    let converter = apply_env_options(converter, None).build()?;
+
+    let cancel = cancel_on_ctrl_c();
+    let report = if args.get(3).map(String::as_str) == Some("--resume") {
+        converter.resume_pages(&cancel).await?
+    } else {
+        converter.migrate_pages(&cancel).await?
+    };
+    print_report(&report);
+    Ok(())
+}
+
+// トークン・データベースの共有・プロパティ・出力先を診断し、問題がなければtrueを返す
+async fn doctor() -> bool {
+    let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+    // 一括変換の絞り込み（移行済み）と並び替え（作成日時）、タグの名前に使うプロパティ
+    let mut requirements = vec![
+        PropertyRequirement::new(properties::MIGRATED, &["checkbox"]),
+        PropertyRequirement::new(properties::CREATED_TIME, &["created_time", "date"]),
+    ];
+    if let Some(checkbox) = env("MIGRATED_CHECKBOX").filter(|name| name != properties::MIGRATED) {
+        requirements.push(PropertyRequirement::new(&checkbox, &["checkbox"]));
+    }
+    let doctor = Doctor::new(env("NOTION_TOKEN"))
+        .with_database("ALL_DATABASE_ID", env("ALL_DATABASE_ID"), requirements)
+        .with_database(
+            "TAG_DATABASE_ID",
+            env("TAG_DATABASE_ID"),
+            vec![PropertyRequirement::new(properties::TAG_NAME, &["title"])],
+        )
+        .with_output_dir(env("OBSIDIAN_DIR").map(PathBuf::from));
+
+    let checks = doctor.run().await;
+    for check in &checks {
+        let mark = match check.status {
+            CheckStatus::Ok => "✓",
+            CheckStatus::Warning => "!",
+            CheckStatus::Error => "✗",
+        };
+        println!("{} {}: {}", mark, check.title, check.detail);
+        if let Some(fix) = &check.fix {
+            println!("    → {}", fix);
+        }
+    }
+
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let (errors, warnings) = (count(CheckStatus::Error), count(CheckStatus::Warning));
+    println!();
+    if errors + warnings == 0 {
+        println!("{}", tr!(Msg::DoctorSummaryOk));
+    } else {
+        println!("{}", tr!(Msg::DoctorSummaryProblems, errors, warnings));
+    }
+    errors == 0
+}
+
+// Ctrl-Cで処理中のページまで変換して中止する（2回目は即座に終了する）
+fn cancel_on_ctrl_c() -> CancellationToken {
+    let cancel = CancellationToken::new();
+    let handle = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("\n{}", tr!(Msg::CancelRequested));
+            handle.cancel();
+        }
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+    cancel
+}
+
+// 変換の進捗を標準エラー出力に表示する
+#[derive(Default)]
+struct ProgressBar {
+    // (完了したページ数, 全ページ数)
+    progress: Mutex<(usize, usize)>,
+}
+
+impl ProgressBar {
+    const WIDTH: usize = 30;
+
+    fn draw(&self, title: &str) {
+        let (done, total) = *self.progress.lock().unwrap();
+        let filled = (Self::WIDTH * done)
+            .checked_div(total)
+            .unwrap_or(Self::WIDTH);
+        eprint!(
+            "\r\x1b[K[{}{}] {}/{} {}",
+            "#".repeat(filled),
+            "-".repeat(Self::WIDTH - filled),
+            done,
+            total,
+            title
+        );
+        let _ = std::io::stderr().flush();
+    }
+
+    fn advance(&self) {
+        self.progress.lock().unwrap().0 += 1;
+    }
+}
+
+impl MigrationObserver for ProgressBar {
+    fn on_event(&self, event: &MigrationEvent) {
+        match event {
+            MigrationEvent::PagesDiscovered { count } => {
+                *self.progress.lock().unwrap() = (0, *count);
+                self.draw("");
+            }
+            MigrationEvent::PageStarted { title, .. } => self.draw(title),
+            MigrationEvent::PageWritten { title, .. } => {
+                self.advance();
+                self.draw(title);
+            }
+            MigrationEvent::PageFailed { title, error, .. } => {
+                self.advance();
+                eprintln!("\r\x1b[K{}", tr!(Msg::PageFailed, title, error));
+                self.draw("");
+            }
+            MigrationEvent::PostProcessFailed { title, error, .. } => {
+                eprintln!("\r\x1b[K{}", tr!(Msg::PostProcessFailed, title, error));
+                self.draw("");
+            }
+            MigrationEvent::RunFinished { .. } => eprintln!(),
+            _ => {}
+        }
+    }
+}
+
+fn print_report(report: &MigrationReport) {
+    println!("{}", tr!(Msg::Finished, report.migrated(), report.total()));
+    if report.cancelled {
+        println!("{}", tr!(Msg::Cancelled));
+    }
+    if report.partially_migrated() > 0 {
+        println!(
+            "{}",
+            tr!(Msg::PartiallyMigrated, report.partially_migrated())
+        );
+    }
+    for result in &report.retried {
+        match &result.outcome {
+            PageOutcome::Migrated => println!("{}", tr!(Msg::RetrySucceeded, result.title)),
+            PageOutcome::PartiallyMigrated { error } | PageOutcome::Failed { error } => {
+                println!("{}", tr!(Msg::RetryFailed, result.title, error))
+            }
+        }
+    }
+}
+
+async fn frontmatter_generator(
+    tag_database_id: &str,
+    token: String,
+    user_directory: &Option<Arc<UserDirectory>>,
+) -> anyhow::Result<MyFrontmatterGenerator> {
+    let mut generator = MyFrontmatterGenerator::new(tag_database_id, token.clone())
+        .await
+        .with_context(|| tr!(Msg::LoadTagsFailed))?;
+    if let Some(date_style) = date_style_from_env() {
+        generator = generator.with_date_style(date_style);
+    }
+    if std::env::var("RESOLVE_RELATIONS").as_deref() == Ok("true") {
+        let resolver = relation_resolver_from_env(generator.title_cache(), token)?;
+        generator = generator.with_relation_resolver(resolver);
+    }
+    generator = generator.with_media_options(media_options_from_env());
+    Ok(match user_directory {
+        Some(users) => generator.with_user_directory(users.clone()),
+        None => generator,
+    })
+}
+
+// RELATION_KEYS: "プロパティ名=キー" のカンマ区切り（例: "タイプ=types,関連=related"）
+// RELATION_CACHE_FILE: リレーション先のタイトルのキャッシュファイル
+// RELATION_CACHE_DAYS: キャッシュしたタイトルを使う日数（デフォルト: 7）
+fn relation_resolver_from_env(
+    titles: Arc<PageTitleCache>,
+    token: String,
+) -> anyhow::Result<RelationResolver> {
+    let mut resolver = RelationResolver::new(titles).with_token(token);
+    if let Ok(keys) = std::env::var("RELATION_KEYS") {
+        for pair in keys.split(',') {
+            if let Some((property, key)) = pair.split_once('=') {
+                resolver = resolver.with_key(property.trim(), key.trim());
+            }
+        }
+    }
+    let days = std::env::var("RELATION_CACHE_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(7);
+    Ok(match std::env::var("RELATION_CACHE_FILE") {
+        Ok(path) => resolver
+            .with_cache_file(PathBuf::from(path), chrono::Duration::days(days))
+            .with_context(|| tr!(Msg::LoadRelationCacheFailed))?,
+        Err(_) => resolver,
+    })
+}
+
+// 変換後にNotionのページに対して行う処理（登録順に実行する）
+// MIGRATED_CHECKBOX: 移行済みにするチェックボックス（デフォルト: 移行済み、空文字で無効）
+// MIGRATED_STATUS_PROPERTY / MIGRATED_STATUS_VALUE: 移行後に設定するステータス
+// MIGRATED_SELECT_PROPERTY / MIGRATED_SELECT_VALUE: 移行後に設定するセレクト
+// NOTE_URL_PROPERTY: 保存先を書き込むURLプロパティ（VAULT_ROOT・VAULT_NAMEを参照）
+// MIGRATED_COMMENT: ページに追加するコメント
+// ARCHIVE_MIGRATED: true の場合ページをアーカイブする
+fn post_processor_chain(obsidian_dir: &str) -> PostProcessorChain {
+    let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+    let mut chain = PostProcessorChain::new();
+
+    let checkbox =
+        std::env::var("MIGRATED_CHECKBOX").unwrap_or_else(|_| properties::MIGRATED.to_string());
+    if !checkbox.is_empty() {
+        chain = chain.with(Box::new(SetCheckbox {
+            property: checkbox,
+            checked: true,
+        }));
+    }
+    for (kind, property, value) in [
+        (
+            OptionPropertyKind::Status,
+            "MIGRATED_STATUS_PROPERTY",
+            "MIGRATED_STATUS_VALUE",
+        ),
+        (
+            OptionPropertyKind::Select,
+            "MIGRATED_SELECT_PROPERTY",
+            "MIGRATED_SELECT_VALUE",
+        ),
+    ] {
+        if let (Some(property), Some(value)) = (env(property), env(value)) {
+            chain = chain.with(Box::new(SetOption {
+                property,
+                kind,
+                value,
+            }));
+        }
+    }
+    if let Some(property) = env("NOTE_URL_PROPERTY") {
+        chain = chain.with(Box::new(WriteNoteUrl {
+            property,
+            vault_root: PathBuf::from(
+                env("VAULT_ROOT").unwrap_or_else(|| obsidian_dir.to_string()),
+            ),
+            vault_name: env("VAULT_NAME"),
+        }));
+    }
+    if let Some(text) = env("MIGRATED_COMMENT") {
+        chain = chain.with(Box::new(AppendComment { text }));
+    }
+    if env("ARCHIVE_MIGRATED").as_deref() == Some("true") {
+        chain = chain.with(Box::new(ArchivePage));
+    }
+    chain
+}
+
+// 環境変数による任意設定をビルダーに反映する
+fn apply_env_options(
+    builder: NotionToObsidianBuilder,
+    user_directory: Option<Arc<UserDirectory>>,
+) -> NotionToObsidianBuilder {
+    let mut builder = builder
+        .with_synced_block_mode(synced_block_mode_from_env())
+        .with_comments_mode(comments_mode_from_env())
+        .with_tasks_mode(std::env::var("TASKS_MODE").as_deref() == Ok("true"))
+        .with_block_anchors(std::env::var("BLOCK_ANCHORS").as_deref() == Ok("true"))
+        .with_media_options(media_options_from_env());
+    if let Some(date_style) = date_style_from_env() {
+        builder = builder.with_date_style(date_style);
+    }
+    if let Some(layout) = column_layout_from_env() {
+        builder = builder.with_column_layout(layout);
+    }
+    match user_directory {
+        Some(users) => builder.with_user_directory(users),
+        None => builder,
+    }
+}
+
+// SYNCED_BLOCK_MODE: inline(デフォルト) / note / anchor
+fn synced_block_mode_from_env() -> SyncedBlockMode {
+    match std::env::var("SYNCED_BLOCK_MODE").as_deref() {
+        Ok("note") => SyncedBlockMode::Note {
+            folder: std::env::var("SYNCED_BLOCK_DIR").unwrap_or_else(|_| "Synced".to_string()),
+        },
+        Ok("anchor") => SyncedBlockMode::Anchor,
+        _ => SyncedBlockMode::Inline,
+    }
+}
+
+// COMMENTS_MODE: disabled(デフォルト) / section / sidecar
+fn comments_mode_from_env() -> CommentsMode {
+    match std::env::var("COMMENTS_MODE").as_deref() {
+        Ok("section") => CommentsMode::Section,
+        Ok("sidecar") => CommentsMode::Sidecar,
+        _ => CommentsMode::Disabled,
+    }
+}
+
+// DATE_STYLE: iso / daily / tasks（未指定の場合は変換しない）
+fn date_style_from_env() -> Option<DateStyle> {
+    match std::env::var("DATE_STYLE").as_deref() {
+        Ok("iso") => Some(DateStyle::Iso),
+        Ok("daily") => Some(match std::env::var("DAILY_NOTE_FORMAT") {
+            Ok(format) => DateStyle::DailyNote { format },
+            Err(_) => DateStyle::daily_note(),
+        }),
+        Ok("tasks") => Some(DateStyle::TasksDue),
+        _ => None,
+    }
+}
+
+// COLUMN_LAYOUT: sequential / multi-column / callouts（未指定の場合は区切りなしで順番に出力）
+fn column_layout_from_env() -> Option<ColumnLayout> {
+    match std::env::var("COLUMN_LAYOUT").as_deref() {
+        Ok("sequential") => Some(ColumnLayout::Sequential),
+        Ok("multi-column") => Some(ColumnLayout::MultiColumn),
+        Ok("callouts") => Some(ColumnLayout::Callouts),
+        _ => None,
+    }
+}
+
+// ATTACHMENTS_DIR: 音声・PDF・ファイルの保存先
+// EMBED_FALLBACK: link(デフォルト) / iframe
+// FETCH_LINK_TITLES: true の場合ブックマークのタイトルを取得する
+// PAGE_ASSETS: true の場合ページのアイコン・カバー画像をダウンロードする
+fn media_options_from_env() -> MediaOptions {
+    MediaOptions {
+        attachments_folder: std::env::var("ATTACHMENTS_DIR").ok(),
+        embed_fallback: match std::env::var("EMBED_FALLBACK").as_deref() {
+            Ok("iframe") => EmbedFallback::Iframe,
+            _ => EmbedFallback::Link,
+        },
+        fetch_titles: std::env::var("FETCH_LINK_TITLES").as_deref() == Ok("true"),
+        page_assets: std::env::var("PAGE_ASSETS").as_deref() == Ok("true"),
+    }
+}
@@ -0,0 +1,156 @@
+use regex::Regex;
+
+// 変換後のMarkdownを整形する
+// - 入れ子のリストを親のマーカー幅に合わせてインデントする
+// - 連続する空行を1行にまとめる
+// - 見出し・表・コードブロックの前後に空行を入れる
+pub fn normalize(markdown: &str) -> String {
+    let markdown = markdown.replace("\r\n", "\n");
+    let lines = reindent_lists(&markdown);
+    let lines = normalize_blank_lines(&lines);
+    format!("{}\n", lines.join("\n").trim_matches('\n'))
+}
+
+struct ListFrame {
+    // 元のマーカーのインデント
+    indent: usize,
+    // 整形後の本文の開始位置
+    content_column: usize,
+    // 最初の子の元のインデント
+    child_indent: Option<usize>,
+}
+
+pub(crate) fn fence_marker(line: &str) -> Option<&'static str> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("```") {
+        Some("```")
+    } else if trimmed.starts_with("~~~") {
+        Some("~~~")
+    } else {
+        None
+    }
+}
+
+pub(crate) fn is_fence_close(line: &str, marker: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with(marker) && trimmed.trim_start_matches(&marker[..1]).is_empty()
+}
+
+fn shift(line: &str, from: usize, to: usize) -> String {
+    let body = &line[from.min(line.len())..];
+    format!("{}{}", " ".repeat(to), body)
+}
+
+fn reindent_lists(markdown: &str) -> Vec<String> {
+    let list_item = Regex::new(r"^( *)([-*+]|\d+[.)]) +").unwrap();
+    let mut stack: Vec<ListFrame> = Vec::new();
+    let mut output = Vec::new();
+    // コードブロック内は開始行と同じだけずらす
+    let mut fence: Option<(&str, usize, usize)> = None;
+
+    for line in markdown.lines() {
+        if let Some((marker, from, to)) = fence {
+            if line.trim().is_empty() {
+                output.push(String::new());
+                continue;
+            }
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            output.push(shift(line, indent, to + indent.saturating_sub(from)));
+            if is_fence_close(line, marker) {
+                fence = None;
+            }
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            output.push(String::new());
+            continue;
+        }
+
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        while stack.last().is_some_and(|frame| indent <= frame.indent) {
+            stack.pop();
+        }
+
+        let new_indent = match stack.last_mut() {
+            Some(frame) => {
+                let child_indent = *frame.child_indent.get_or_insert(indent);
+                frame.content_column + indent.saturating_sub(child_indent)
+            }
+            None => indent,
+        };
+
+        if let Some(marker) = fence_marker(line) {
+            fence = Some((marker, indent, new_indent));
+        } else if let Some(captures) = list_item.captures(line) {
+            let marker_width = captures[0].len() - indent;
+            stack.push(ListFrame {
+                indent,
+                content_column: new_indent + marker_width,
+                child_indent: None,
+            });
+        }
+
+        output.push(shift(line, indent, new_indent));
+    }
+
+    output
+}
+
+fn is_heading(line: &str) -> bool {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&hashes) && line[hashes..].starts_with(' ')
+}
+
+fn is_table_row(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
+fn normalize_blank_lines(lines: &[String]) -> Vec<String> {
+    let mut output: Vec<String> = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut blank_after = false;
+    let mut in_table = false;
+
+    let ensure_blank = |output: &mut Vec<String>| {
+        if output.last().is_some_and(|line| !line.is_empty()) {
+            output.push(String::new());
+        }
+    };
+
+    for line in lines {
+        if let Some(marker) = fence {
+            output.push(line.clone());
+            if is_fence_close(line, marker) {
+                fence = None;
+                blank_after = true;
+            }
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            in_table = false;
+            ensure_blank(&mut output);
+            continue;
+        }
+
+        let table_row = is_table_row(line);
+        if blank_after || (in_table && !table_row) || (table_row && !in_table) {
+            ensure_blank(&mut output);
+        }
+        blank_after = false;
+        in_table = table_row;
+
+        if let Some(marker) = fence_marker(line) {
+            ensure_blank(&mut output);
+            fence = Some(marker);
+        } else if is_heading(line) {
+            ensure_blank(&mut output);
+            blank_after = true;
+        }
+
+        output.push(line.clone());
+    }
+
+    output
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+use notion_client::objects::page::Page;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{
+    blocks::{compact_id, BlockNode},
+    converter::sanitize_filename,
+    error::{NotionToObsidianError, Result},
+    i18n::Msg,
+    renderer::{block_kind, block_value, caption, file_url},
+    tr,
+    traits::frontmatter_generator::yaml_string,
+    vault,
+};
+
+// YouTube・Vimeo以外の埋め込みの出力方法
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum EmbedFallback {
+    #[default]
+    Link,
+    Iframe,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MediaOptions {
+    // 音声・PDF・ファイルのダウンロード先（Noneの場合はダウンロードせずリンクにする）
+    pub attachments_folder: Option<String>,
+    pub embed_fallback: EmbedFallback,
+    // キャプションのないブックマーク・埋め込みのタイトルをページから取得する
+    pub fetch_titles: bool,
+    // ページのアイコン・カバー画像をattachments_folderにダウンロードする
+    pub page_assets: bool,
+}
+
+// レンダリング時に参照するダウンロード済みファイルと取得したタイトル
+#[derive(Debug, Clone, Default)]
+pub struct MediaIndex {
+    pub files: HashMap<String, String>,
+    pub titles: HashMap<String, String>,
+    pub embed_fallback: EmbedFallback,
+}
+
+impl MediaIndex {
+    pub fn local_file(&self, url: &str) -> Option<&str> {
+        self.files.get(url).map(String::as_str)
+    }
+
+    pub fn title(&self, url: &str) -> Option<&str> {
+        self.titles.get(url).map(String::as_str)
+    }
+}
+
+pub fn youtube_id(url: &str) -> Option<String> {
+    let pattern = Regex::new(
+        r"(?:youtube\.com/(?:watch\?(?:.*&)?v=|embed/|shorts/)|youtu\.be/)([A-Za-z0-9_-]{11})",
+    )
+    .unwrap();
+    pattern
+        .captures(url)
+        .map(|captures| captures[1].to_string())
+}
+
+pub fn vimeo_id(url: &str) -> Option<String> {
+    let pattern = Regex::new(r"vimeo\.com/(?:video/)?(\d+)").unwrap();
+    pattern
+        .captures(url)
+        .map(|captures| captures[1].to_string())
+}
+
+fn iframe(src: &str) -> String {
+    format!(
+        "<iframe src=\"{}\" width=\"560\" height=\"315\" frameborder=\"0\" allowfullscreen></iframe>",
+        src
+    )
+}
+
+// YouTube・VimeoはObsidianで再生できるiframeにする
+pub fn player(url: &str) -> Option<String> {
+    if let Some(id) = youtube_id(url) {
+        return Some(iframe(&format!("https://www.youtube.com/embed/{}", id)));
+    }
+    vimeo_id(url).map(|id| iframe(&format!("https://player.vimeo.com/video/{}", id)))
+}
+
+pub fn link(label: &str, url: &str) -> String {
+    if label.is_empty() {
+        format!("[{}]({})", url, url)
+    } else {
+        format!("[{}]({})", label, url)
+    }
+}
+
+pub fn render_embed(url: &str, label: &str, media: &MediaIndex) -> String {
+    if let Some(player) = player(url) {
+        return player;
+    }
+    match media.embed_fallback {
+        EmbedFallback::Iframe => format!(
+            "<iframe src=\"{}\" width=\"100%\" height=\"400\" frameborder=\"0\"></iframe>",
+            url
+        ),
+        EmbedFallback::Link => link(label, url),
+    }
+}
+
+fn needs_download(kind: &str, value: &serde_json::Value, url: &str) -> bool {
+    match kind {
+        "audio" | "pdf" | "file" => true,
+        // 動画はNotionにアップロードされたもののみ
+        "video" => {
+            player(url).is_none() && value.get("type").and_then(|t| t.as_str()) == Some("file")
+        }
+        _ => false,
+    }
+}
+
+fn attachment_name(block_id: &str, url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let name = sanitize_filename(path.rsplit('/').next().unwrap_or_default());
+    if name.is_empty() {
+        compact_id(block_id)
+    } else {
+        format!("{}-{}", compact_id(block_id), name)
+    }
+}
+
+async fn download(url: &str, dir: &Path, filename: &str) -> Result<()> {
+    let download_error = |source| NotionToObsidianError::DownloadError {
+        url: url.to_string(),
+        source,
+    };
+    let bytes = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(download_error)?
+        .bytes()
+        .await
+        .map_err(download_error)?;
+
+    fs::create_dir_all(dir).map_err(|e| NotionToObsidianError::file_write(dir, e))?;
+    vault::write_atomic(&dir.join(filename), &bytes)
+}
+
+async fn fetch_title(url: &str) -> Option<String> {
+    let html = reqwest::get(url).await.ok()?.text().await.ok()?;
+    let pattern = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+    let title = pattern.captures(&html)?[1]
+        .trim()
+        .replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">");
+    (!title.is_empty()).then_some(title)
+}
+
+// メディアブロックのファイルをダウンロードし、リンクのタイトルを取得する
+// 失敗した場合はリモートのURLのまま出力するため、エラーにはしない
+pub async fn collect(tree: &[BlockNode], vault_dir: &Path, options: &MediaOptions) -> MediaIndex {
+    let mut index = MediaIndex {
+        embed_fallback: options.embed_fallback.clone(),
+        ..Default::default()
+    };
+
+    let mut targets = Vec::new();
+    BlockNode::walk(tree, &mut |node| {
+        let value = block_value(&node.block);
+        if let Some(url) = file_url(&value) {
+            targets.push((node.id().to_string(), block_kind(&node.block), value, url));
+        }
+    });
+
+    for (block_id, kind, value, url) in targets {
+        if let Some(folder) = &options.attachments_folder {
+            if needs_download(&kind, &value, &url) {
+                let filename = attachment_name(&block_id, &url);
+                match download(&url, &vault_dir.join(folder), &filename).await {
+                    Ok(_) => {
+                        index.files.insert(url.clone(), filename);
+                    }
+                    Err(e) => warn!("{}", tr!(Msg::FileDownloadFailed, url, e)),
+                }
+            }
+        }
+
+        let titled = matches!(kind.as_str(), "bookmark" | "link_preview" | "embed");
+        if options.fetch_titles && titled && caption(&value).is_empty() {
+            if let Some(title) = fetch_title(&url).await {
+                index.titles.insert(url, title);
+            }
+        }
+    }
+
+    index
+}
+
+// フロントマターに出力するページのアイコンとカバー画像
+// IconizeプラグインはiconをBannersプラグインはbannerを参照する
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageAssets {
+    pub icon: Option<String>,
+    pub banner: Option<String>,
+}
+
+fn page_icon_url(page: &Page) -> Option<String> {
+    let value = serde_json::to_value(&page.icon).ok()?;
+    value
+        .get("custom_emoji")
+        .and_then(|emoji| emoji.get("url"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| file_url(&value))
+}
+
+fn page_cover_url(page: &Page) -> Option<String> {
+    file_url(&serde_json::to_value(&page.cover).ok()?)
+}
+
+impl PageAssets {
+    // カスタムアイコンとカバー画像はattachments_folderにダウンロードしたファイルを参照する
+    // 未指定の場合は出力しない（Notionにアップロードされた画像のURLは一定時間で無効になるため）
+    pub fn from_page(page: &Page, attachments_folder: Option<&str>) -> Self {
+        let local = |url: String| {
+            attachments_folder
+                .map(|folder| format!("{}/{}", folder, attachment_name(&page.id, &url)))
+        };
+
+        let emoji = serde_json::to_value(&page.icon).ok().and_then(|value| {
+            value
+                .get("emoji")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        });
+        let icon = emoji.or_else(|| page_icon_url(page).and_then(local));
+        let banner = page_cover_url(page)
+            .and_then(local)
+            .map(|path| format!("![[{}]]", path));
+
+        Self { icon, banner }
+    }
+
+    pub fn frontmatter(&self) -> String {
+        let mut frontmatter = String::new();
+        if let Some(icon) = &self.icon {
+            frontmatter.push_str(&format!("icon: {}\n", yaml_string(icon)));
+        }
+        if let Some(banner) = &self.banner {
+            frontmatter.push_str(&format!("banner: {}\n", yaml_string(banner)));
+        }
+        frontmatter
+    }
+}
+
+// アイコン（絵文字以外）とカバー画像をダウンロードし、ダウンロードしたURLと保存先を返す
+// 失敗した場合はフロントマターのリンク先がないだけなのでエラーにはしない
+pub async fn download_page_assets(
+    page: &Page,
+    vault_dir: &Path,
+    attachments_folder: &str,
+) -> Vec<(String, PathBuf)> {
+    let mut downloaded = Vec::new();
+    for url in [page_icon_url(page), page_cover_url(page)]
+        .into_iter()
+        .flatten()
+    {
+        let filename = attachment_name(&page.id, &url);
+        let path = vault_dir.join(attachments_folder).join(&filename);
+        if path.exists() {
+            continue;
+        }
+        match download(&url, &vault_dir.join(attachments_folder), &filename).await {
+            Ok(_) => downloaded.push((url, path)),
+            Err(e) => warn!("{}", tr!(Msg::FileDownloadFailed, url, e)),
+        }
+    }
+    downloaded
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{NotionToObsidianError, Result},
+    vault,
+};
+
+// 変換の状態を保存するフォルダ（出力先の直下、Obsidianでは表示されない）
+pub const STATE_DIR: &str = ".notion-to-obsidian";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageOutcome {
+    // 変換・保存・後処理のすべてが成功
+    Migrated,
+    // ノートは保存したが後処理（移行済みフラグの更新など）に失敗
+    // ジャーナルに記録し、次回の実行で後処理を再試行する
+    PartiallyMigrated { error: String },
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageResult {
+    pub page_id: String,
+    pub title: String,
+    pub outcome: PageOutcome,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub pages: Vec<PageResult>,
+    // 前回までに中断・失敗したページの後処理の再試行の結果
+    pub retried: Vec<PageResult>,
+    // 途中で中止した場合（残りのページはチェックポイントに記録される）
+    pub cancelled: bool,
+}
+
+impl MigrationReport {
+    fn count(results: &[PageResult], matches: impl Fn(&PageOutcome) -> bool) -> usize {
+        results.iter().filter(|r| matches(&r.outcome)).count()
+    }
+
+    pub fn total(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn migrated(&self) -> usize {
+        Self::count(&self.pages, |o| matches!(o, PageOutcome::Migrated))
+    }
+
+    pub fn partially_migrated(&self) -> usize {
+        Self::count(&self.pages, |o| {
+            matches!(o, PageOutcome::PartiallyMigrated { .. })
+        })
+    }
+
+    pub fn failed(&self) -> usize {
+        Self::count(&self.pages, |o| matches!(o, PageOutcome::Failed { .. }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalStage {
+    // ノートの書き込み中（書き込み前に記録する）
+    Writing,
+    // ノートは書き込んだが、Notionへの反映（後処理）が済んでいない
+    Marking,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub title: String,
+    pub note: PathBuf,
+    pub stage: JournalStage,
+    #[serde(default)]
+    pub error: String,
+    // 完了した後処理のPostProcessor::step_id（再試行では実行しない）
+    #[serde(default)]
+    pub completed: Vec<String>,
+}
+
+// ページごとの処理（変換→書き込み→Notionへの反映）の途中経過
+// 完了したページは削除するため、残っているエントリは中断または失敗したもの
+#[derive(Debug, Default)]
+pub struct Journal {
+    path: PathBuf,
+    entries: BTreeMap<String, JournalEntry>,
+}
+
+impl Journal {
+    pub fn load(obsidian_dir: &Path) -> Result<Self> {
+        let path = obsidian_dir.join(STATE_DIR).join("journal.json");
+        let entries = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| NotionToObsidianError::file_read(&path, e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| NotionToObsidianError::file_read(&path, e))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, entries })
+    }
+
+    pub fn entries(&self) -> &BTreeMap<String, JournalEntry> {
+        &self.entries
+    }
+
+    pub fn record(&mut self, page_id: &str, entry: JournalEntry) -> Result<()> {
+        self.entries.insert(page_id.to_string(), entry);
+        self.save()
+    }
+
+    pub fn remove(&mut self, page_id: &str) -> Result<()> {
+        if self.entries.remove(page_id).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        if self.entries.is_empty() {
+            if self.path.exists() {
+                fs::remove_file(&self.path)
+                    .map_err(|e| NotionToObsidianError::file_write(&self.path, e))?;
+            }
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| NotionToObsidianError::file_write(dir, e))?;
+        }
+        let content = serde_json::to_string_pretty(&self.entries)
+            .map_err(|e| NotionToObsidianError::file_write(&self.path, e))?;
+        vault::write_atomic(&self.path, content)
+    }
+}
+
+// 一括変換の中止を伝える（クローンしたトークンはすべて同じ状態を共有する）
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 処理中のページは最後まで変換し、次のページに進む前に中止する
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+// 一括変換の進み具合（処理済みのページと残りのページ）
+// 最後まで処理すると削除するため、残っている場合は中断した変換がある
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub completed: Vec<String>,
+    pub pending: Vec<String>,
+}
+
+impl Checkpoint {
+    pub fn new(pending: Vec<String>) -> Self {
+        Self {
+            completed: Vec::new(),
+            pending,
+        }
+    }
+
+    fn path(obsidian_dir: &Path) -> PathBuf {
+        obsidian_dir.join(STATE_DIR).join("checkpoint.json")
+    }
+
+    pub fn exists(obsidian_dir: &Path) -> bool {
+        Self::path(obsidian_dir).exists()
+    }
+
+    pub fn load(obsidian_dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path(obsidian_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| NotionToObsidianError::file_read(&path, e))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| NotionToObsidianError::file_read(&path, e))
+    }
+
+    pub fn save(&self, obsidian_dir: &Path) -> Result<()> {
+        let path = Self::path(obsidian_dir);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| NotionToObsidianError::file_write(dir, e))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| NotionToObsidianError::file_write(&path, e))?;
+        vault::write_atomic(&path, content)
+    }
+
+    pub fn remove(obsidian_dir: &Path) -> Result<()> {
+        let path = Self::path(obsidian_dir);
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| NotionToObsidianError::file_write(&path, e))?;
+        }
+        Ok(())
+    }
+
+    // 失敗したページも処理済みにする（移行済みにならないため、通常の変換で再び対象になる）
+    pub fn complete(&mut self, page_id: &str) {
+        self.pending.retain(|id| id != page_id);
+        self.completed.push(page_id.to_string());
+    }
+}
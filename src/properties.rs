@@ -0,0 +1,8 @@
+// 一括変換・後処理・タグの読み込みが前提とするNotionのプロパティ名
+
+// 一括変換は未チェックのページを対象とし、後処理でチェックする
+pub const MIGRATED: &str = "移行済み";
+// 一括変換の並び順（新しい順）
+pub const CREATED_TIME: &str = "作成日時";
+// タグデータベースのタイトル
+pub const TAG_NAME: &str = "名前";
@@ -0,0 +1,287 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+use notion_client::{
+    endpoints::Client,
+    objects::page::{Page, PageProperty},
+};
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    api,
+    converter::{extract_page_title, sanitize_filename},
+    error::{NotionToObsidianError, Result},
+    i18n::Msg,
+    tr,
+    traits::frontmatter_generator::yaml_string,
+    vault,
+};
+
+// リレーション先などのページIDからタイトルへのキャッシュ
+// フロントマターの生成で必要になったときに取得し、複数のジェネレーターで共有できる
+#[derive(Debug, Default)]
+pub struct PageTitleCache {
+    titles: Mutex<HashMap<String, CachedTitle>>,
+}
+
+// Notionでタイトルを変更した場合に反映されるよう、取得した日時を記録して古いものは読み込まない
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedTitle {
+    title: String,
+    fetched_at: DateTime<Utc>,
+}
+
+// 以前の形式（タイトルのみ）のキャッシュは取得日時がわからないため読み込まない
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CacheEntry {
+    Title(CachedTitle),
+    Other(#[allow(dead_code)] IgnoredAny),
+}
+
+fn key(page_id: &str) -> String {
+    page_id.replace('-', "")
+}
+
+impl PageTitleCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, page_id: &str) -> Option<String> {
+        let titles = self.titles.lock().unwrap();
+        titles.get(&key(page_id)).map(|cached| cached.title.clone())
+    }
+
+    pub fn insert(&self, page_id: &str, title: String) {
+        let cached = CachedTitle {
+            title,
+            fetched_at: Utc::now(),
+        };
+        self.titles.lock().unwrap().insert(key(page_id), cached);
+    }
+
+    // 以前の実行で保存したキャッシュのうち、max_age以内に取得したものを読み込む（ファイルがなければ何もしない）
+    pub fn load_file(&self, path: &Path, max_age: Duration) -> Result<()> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let content =
+            fs::read_to_string(path).map_err(|e| NotionToObsidianError::file_read(path, e))?;
+        let entries: HashMap<String, CacheEntry> = serde_json::from_str(&content)
+            .map_err(|e| NotionToObsidianError::file_read(path, e))?;
+        let expires = Utc::now() - max_age;
+        let titles = entries.into_iter().filter_map(|(id, entry)| match entry {
+            CacheEntry::Title(cached) if cached.fetched_at > expires => Some((id, cached)),
+            _ => None,
+        });
+        self.titles.lock().unwrap().extend(titles);
+        Ok(())
+    }
+
+    pub fn save_file(&self, path: &Path) -> Result<()> {
+        // 差分を確認しやすいようにキーの順に書き出す
+        let titles: BTreeMap<String, CachedTitle> = self
+            .titles
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, title)| (id.clone(), title.clone()))
+            .collect();
+        let content = serde_json::to_string_pretty(&titles)
+            .map_err(|e| NotionToObsidianError::file_write(path, e))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| NotionToObsidianError::file_write(dir, e))?;
+        }
+        vault::write_atomic(path, content)
+    }
+
+    // キャッシュになければページを取得する（タイトルのないページはNone）
+    pub async fn resolve(&self, client: &Client, page_id: &str) -> Result<Option<String>> {
+        if let Some(title) = self.get(page_id) {
+            return Ok(Some(title));
+        }
+
+        let page = client
+            .pages
+            .retrieve_a_page(page_id, None)
+            .await
+            .map_err(|e| NotionToObsidianError::page_retrieval(page_id, e))?;
+        let title = extract_page_title(&page);
+        if let Some(title) = &title {
+            self.insert(page_id, title.clone());
+        }
+        Ok(title)
+    }
+}
+
+// リレーションプロパティをリレーション先のページへのWikiリンクのリストとして出力する
+// リレーション先はどのデータベースのページでもよい
+pub struct RelationResolver {
+    titles: Arc<PageTitleCache>,
+    // プロパティ名からフロントマターのキー（未指定の場合はプロパティ名をそのまま使う）
+    keys: HashMap<String, String>,
+    // 取得したタイトルを次回の実行でも使えるように保存する
+    cache_file: Option<PathBuf>,
+    // ページのオブジェクトに含まれない26件目以降のリレーションを取得する
+    // （notion_clientはプロパティのページ送りに対応していないため、APIを直接呼び出す）
+    token: Option<String>,
+}
+
+impl RelationResolver {
+    pub fn new(titles: Arc<PageTitleCache>) -> Self {
+        Self {
+            titles,
+            keys: HashMap::new(),
+            cache_file: None,
+            token: None,
+        }
+    }
+
+    pub fn with_token(self, token: String) -> RelationResolver {
+        RelationResolver {
+            token: Some(token),
+            ..self
+        }
+    }
+
+    pub fn with_key(mut self, property: &str, key: &str) -> RelationResolver {
+        self.keys.insert(property.to_string(), key.to_string());
+        self
+    }
+
+    // max_ageより前に取得したタイトルは取得し直す
+    pub fn with_cache_file(
+        self,
+        cache_file: PathBuf,
+        max_age: Duration,
+    ) -> Result<RelationResolver> {
+        self.titles.load_file(&cache_file, max_age)?;
+        Ok(RelationResolver {
+            cache_file: Some(cache_file),
+            ..self
+        })
+    }
+
+    pub fn key<'a>(&'a self, property: &'a str) -> &'a str {
+        self.keys
+            .get(property)
+            .map(String::as_str)
+            .unwrap_or(property)
+    }
+
+    // 一括変換の最後にキャッシュを保存する（FrontmatterGenerator::finishから呼ぶ）
+    pub fn save(&self) -> Result<()> {
+        match &self.cache_file {
+            Some(path) => self.titles.save_file(path),
+            None => Ok(()),
+        }
+    }
+
+    async fn title(&self, client: &Client, page_id: &str) -> Option<String> {
+        match self.titles.resolve(client, page_id).await {
+            Ok(title) => title,
+            Err(e) => {
+                warn!("{}", tr!(Msg::RelationPageFetchFailed, page_id, e));
+                None
+            }
+        }
+    }
+
+    pub async fn frontmatter(&self, page: &Page, client: &Client) -> String {
+        let mut relations: Vec<(&String, &PageProperty)> = page
+            .properties
+            .iter()
+            .filter(|(_, property)| matches!(property, PageProperty::Relation { .. }))
+            .collect();
+        relations.sort_by_key(|(name, _)| self.key(name).to_string());
+
+        let mut frontmatter = String::new();
+        for (name, property) in relations {
+            frontmatter.push_str(&format!("{}:\n", yaml_string(self.key(name))));
+            for id in self.relation_ids(&page.id, property).await {
+                if let Some(title) = self.title(client, &id).await {
+                    let link = format!("[[{}]]", sanitize_filename(&title));
+                    frontmatter.push_str(&format!("  - {}\n", yaml_string(&link)));
+                }
+            }
+        }
+        frontmatter
+    }
+
+    // ページのオブジェクトには25件までしか含まれないため、has_moreの場合はプロパティのAPIから全件を取得する
+    async fn relation_ids(&self, page_id: &str, property: &PageProperty) -> Vec<String> {
+        let PageProperty::Relation { relation, .. } = property else {
+            return Vec::new();
+        };
+        let ids: Vec<String> = relation.iter().map(|related| related.id.clone()).collect();
+
+        let value = serde_json::to_value(property).unwrap_or_default();
+        let has_more = value.get("has_more").and_then(Value::as_bool) == Some(true);
+        let (true, Some(property_id), Some(token)) = (
+            has_more,
+            value.get("id").and_then(Value::as_str),
+            &self.token,
+        ) else {
+            return ids;
+        };
+        match fetch_relation_ids(token, page_id, property_id).await {
+            Ok(all) => all,
+            Err(e) => {
+                warn!("{}", tr!(Msg::RelationFetchFailed, page_id, e));
+                ids
+            }
+        }
+    }
+}
+
+async fn fetch_relation_ids(token: &str, page_id: &str, property_id: &str) -> Result<Vec<String>> {
+    let path = format!("/pages/{}/properties/{}", page_id, property_id);
+    let mut ids = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let mut query = vec![("page_size", "100")];
+        if let Some(cursor) = &cursor {
+            query.push(("start_cursor", cursor));
+        }
+        let body = api::get(token, &path, &query).await.map_err(|source| {
+            NotionToObsidianError::PageRetrievalError {
+                page_id: page_id.to_string(),
+                source,
+            }
+        })?;
+        let response: Value = serde_json::from_str(&body)
+            .map_err(|e| NotionToObsidianError::ConversionError(e.to_string()))?;
+
+        let (page, next_cursor) = relation_items(&response);
+        ids.extend(page);
+        match next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    Ok(ids)
+}
+
+// プロパティのAPIのレスポンス（property_itemのリスト）からリレーション先のIDと次のページのカーソルを取り出す
+pub fn relation_items(response: &Value) -> (Vec<String>, Option<String>) {
+    let ids = response["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item["relation"]["id"].as_str().map(str::to_string))
+        .collect();
+    let next_cursor = match response["has_more"].as_bool() {
+        Some(true) => response["next_cursor"].as_str().map(str::to_string),
+        _ => None,
+    };
+    (ids, next_cursor)
+}
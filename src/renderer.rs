@@ -0,0 +1,351 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use notion_client::objects::{
+    block::{Block, BlockType},
+    rich_text::{Annotations, Mention, RichText},
+};
+use serde_json::Value;
+
+use crate::{
+    blocks::BlockNode,
+    converter::sanitize_filename,
+    dates::{self, DateStyle},
+    media::MediaIndex,
+    traits::block_renderer::{BlockRenderer, DefaultBlockRenderer, RenderContext},
+    users::UserDirectory,
+};
+
+// ページごとに変わる出力の設定（IDはハイフンなし）
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    pub media: MediaIndex,
+    // 展開せずに埋め込みに置き換える同期ブロック
+    pub synced_embeds: HashMap<String, String>,
+    // ^id を付けるブロック
+    pub anchors: HashSet<String>,
+    // コメントの脚注のラベル
+    pub footnotes: HashMap<String, String>,
+    // 指定するとユーザーのメンションを人物ノートへのリンクにする
+    pub user_directory: Option<Arc<UserDirectory>>,
+    // 指定すると日付のメンションをその表記にする
+    pub date_style: Option<DateStyle>,
+    // チェックボックスをTasksプラグインの形式で出力する
+    pub tasks_mode: bool,
+}
+
+// ブロックの種類ごとにレンダラーを登録し、未登録の種類はデフォルトで出力する
+pub struct MarkdownRenderer {
+    renderers: HashMap<String, Box<dyn BlockRenderer>>,
+    default_renderer: DefaultBlockRenderer,
+}
+
+impl Default for MarkdownRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownRenderer {
+    pub fn new() -> Self {
+        Self {
+            renderers: HashMap::new(),
+            default_renderer: DefaultBlockRenderer,
+        }
+    }
+
+    // block_typeはNotion APIのtype名（"paragraph", "heading_1", "video"など）
+    pub fn register(&mut self, block_type: &str, renderer: Box<dyn BlockRenderer>) {
+        self.renderers.insert(block_type.to_string(), renderer);
+    }
+
+    pub fn render(&self, nodes: &[BlockNode]) -> String {
+        self.render_with(nodes, &RenderOptions::default())
+    }
+
+    // ダウンロード済みのファイルなど、ページごとの設定を参照しながら出力する
+    pub fn render_with(&self, nodes: &[BlockNode], options: &RenderOptions) -> String {
+        let mut output = String::new();
+        let mut previous: Option<String> = None;
+        let mut list_number = 0;
+
+        for node in nodes {
+            let kind = block_kind(&node.block);
+            list_number = match (&previous, kind.as_str()) {
+                (Some(prev), "numbered_list_item") if prev == "numbered_list_item" => {
+                    list_number + 1
+                }
+                (_, "numbered_list_item") => 1,
+                _ => 0,
+            };
+
+            let context = RenderContext::new(self, list_number, options);
+            let rendered = match self.renderers.get(&kind) {
+                Some(renderer) => renderer.render(node, &context),
+                None => self.default_renderer.render(node, &context),
+            };
+            let rendered = rendered.trim_end_matches('\n');
+            // 空の段落などは出力しない
+            if rendered.trim().is_empty() {
+                continue;
+            }
+
+            if let Some(prev) = &previous {
+                // 同じ種類のリストは詰めて出力し、それ以外のブロックは空行で区切る
+                match (list_group(prev), list_group(&kind)) {
+                    (Some(a), Some(b)) if a == b => output.push('\n'),
+                    _ => output.push_str("\n\n"),
+                }
+            }
+            output.push_str(rendered);
+            previous = Some(kind);
+        }
+
+        output
+    }
+}
+
+// 本文（rich_text）を先頭の行に出力するブロック（脚注を付けられる）
+pub fn is_text_block(kind: &str) -> bool {
+    matches!(
+        kind,
+        "paragraph"
+            | "heading_1"
+            | "heading_2"
+            | "heading_3"
+            | "bulleted_list_item"
+            | "numbered_list_item"
+            | "to_do"
+            | "toggle"
+            | "quote"
+            | "callout"
+    )
+}
+
+fn list_group(kind: &str) -> Option<&'static str> {
+    match kind {
+        "bulleted_list_item" | "toggle" | "to_do" => Some("bullet"),
+        "numbered_list_item" => Some("numbered"),
+        _ => None,
+    }
+}
+
+// ブロックの種類（Notion APIのtype名）
+pub fn block_kind(block: &Block) -> String {
+    let kind = match &block.block_type {
+        BlockType::Paragraph { .. } => "paragraph",
+        BlockType::Heading1 { .. } => "heading_1",
+        BlockType::Heading2 { .. } => "heading_2",
+        BlockType::Heading3 { .. } => "heading_3",
+        BlockType::BulletedListItem { .. } => "bulleted_list_item",
+        BlockType::NumberedListItem { .. } => "numbered_list_item",
+        BlockType::ToDo { .. } => "to_do",
+        BlockType::Toggle { .. } => "toggle",
+        BlockType::Quote { .. } => "quote",
+        BlockType::Callout { .. } => "callout",
+        BlockType::Code { .. } => "code",
+        BlockType::Divider { .. } => "divider",
+        BlockType::Equation { .. } => "equation",
+        BlockType::Image { .. } => "image",
+        BlockType::Video { .. } => "video",
+        BlockType::Pdf { .. } => "pdf",
+        BlockType::File { .. } => "file",
+        BlockType::Embed { .. } => "embed",
+        BlockType::Bookmark { .. } => "bookmark",
+        BlockType::LinkPreview { .. } => "link_preview",
+        BlockType::Table { .. } => "table",
+        BlockType::TableRow { .. } => "table_row",
+        BlockType::ChildPage { .. } => "child_page",
+        BlockType::ChildDatabase { .. } => "child_database",
+        BlockType::ColumnList { .. } => "column_list",
+        BlockType::Column { .. } => "column",
+        BlockType::SyncedBlock { .. } => "synced_block",
+        // 上記以外（音声など）はシリアライズしたときのtypeを読む
+        _ => return serialized_kind(block),
+    };
+    kind.to_string()
+}
+
+fn serialized_kind(block: &Block) -> String {
+    match serde_json::to_value(&block.block_type) {
+        Ok(Value::Object(map)) => match map.get("type").and_then(Value::as_str) {
+            Some(kind) => kind.to_string(),
+            None => map.keys().next().cloned().unwrap_or_default(),
+        },
+        _ => String::new(),
+    }
+}
+
+// ブロックの種類ごとの値（Notion APIの "<type>": {...} の部分）
+pub fn block_value(block: &Block) -> Value {
+    let kind = block_kind(block);
+    let value = serde_json::to_value(&block.block_type).unwrap_or_default();
+    let inner = value.get(&kind).cloned().unwrap_or_default();
+    match inner.get(&kind) {
+        Some(nested) => nested.clone(),
+        None => inner,
+    }
+}
+
+// 画像・動画・ファイルなどのURL
+pub fn file_url(value: &Value) -> Option<String> {
+    value
+        .get("external")
+        .or_else(|| value.get("file"))
+        .and_then(|file| file.get("url"))
+        .or_else(|| value.get("url"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+pub fn caption(value: &Value) -> String {
+    value
+        .get("caption")
+        .cloned()
+        .and_then(|caption| serde_json::from_value::<Vec<RichText>>(caption).ok())
+        .map(|caption| rich_text_to_markdown(&caption))
+        .unwrap_or_default()
+}
+
+fn annotations(rich_text: &RichText) -> Option<&Annotations> {
+    match rich_text {
+        RichText::Text { annotations, .. } => annotations.as_ref(),
+        RichText::Mention { annotations, .. } | RichText::Equation { annotations, .. } => {
+            Some(annotations)
+        }
+        RichText::None => None,
+    }
+}
+
+fn annotate(text: &str, rich_text: &RichText) -> String {
+    let Some(annotations) = annotations(rich_text) else {
+        return text.to_string();
+    };
+
+    // 前後の空白を含めて装飾するとMarkdownとして解釈されないため外に出す
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+
+    let mut decorated = trimmed.to_string();
+    if annotations.code {
+        decorated = format!("`{}`", decorated);
+    }
+    if annotations.bold {
+        decorated = format!("**{}**", decorated);
+    }
+    if annotations.italic {
+        decorated = format!("*{}*", decorated);
+    }
+    if annotations.strikethrough {
+        decorated = format!("~~{}~~", decorated);
+    }
+    format!("{}{}{}", leading, decorated, trailing)
+}
+
+pub fn rich_text_to_markdown(rich_text: &[RichText]) -> String {
+    render_rich_text(rich_text, &RenderOptions::default())
+}
+
+// メンションはページごとの設定に従って出力する
+pub fn render_rich_text(rich_text: &[RichText], options: &RenderOptions) -> String {
+    rich_text
+        .iter()
+        .map(|rt| {
+            let plain_text = rt.plain_text().unwrap_or_default();
+            match rt {
+                RichText::Equation { .. } => format!("${}$", plain_text),
+                RichText::Mention {
+                    mention: Mention::Page { .. },
+                    ..
+                } => format!("[[{}]]", sanitize_filename(&plain_text)),
+                RichText::Mention {
+                    mention: Mention::User { user },
+                    ..
+                } => match &options.user_directory {
+                    Some(user_directory) => user_directory.link(user),
+                    None => plain_text,
+                },
+                RichText::Mention {
+                    mention: Mention::Date { date },
+                    ..
+                } => options
+                    .date_style
+                    .as_ref()
+                    .and_then(|style| dates::render_mention(date, style))
+                    .unwrap_or(plain_text),
+                RichText::Mention { .. } => plain_text,
+                RichText::Text {
+                    href: Some(href), ..
+                } => format!("[{}]({})", annotate(&plain_text, rt), href),
+                _ => annotate(&plain_text, rt),
+            }
+        })
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// HTMLの表のセル用（HTML内ではMarkdownが解釈されないため）
+pub fn rich_text_to_html(rich_text: &[RichText]) -> String {
+    rich_text
+        .iter()
+        .map(|rt| {
+            let mut html = escape_html(&rt.plain_text().unwrap_or_default());
+            if let Some(annotations) = annotations(rt) {
+                if annotations.code {
+                    html = format!("<code>{}</code>", html);
+                }
+                if annotations.bold {
+                    html = format!("<strong>{}</strong>", html);
+                }
+                if annotations.italic {
+                    html = format!("<em>{}</em>", html);
+                }
+                if annotations.strikethrough {
+                    html = format!("<s>{}</s>", html);
+                }
+            }
+            if let RichText::Text {
+                href: Some(href), ..
+            } = rt
+            {
+                html = format!("<a href=\"{}\">{}</a>", escape_html(href), html);
+            }
+            html.replace('\n', "<br>")
+        })
+        .collect()
+}
+
+// 空行以外の各行をインデントする
+pub fn indent(text: &str, width: usize) -> String {
+    let padding = " ".repeat(width);
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", padding, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// すべての行に接頭辞を付ける（引用・コールアウト用）
+pub fn prefix_lines(text: &str, prefix: &str) -> String {
+    text.lines()
+        .map(|line| format!("{}{}", prefix, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,61 @@
+use notion_client::objects::block::{BlockType, SyncedFrom};
+
+use crate::blocks::{compact_id, BlockNode};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SyncedBlockMode {
+    // 参照先の内容をそのままページに展開する（従来の挙動）
+    #[default]
+    Inline,
+    // オリジナルを1つのノートとして書き出し、各参照を ![[ノート]] で埋め込む
+    Note {
+        folder: String,
+    },
+    // オリジナルの位置に ^id を付与し、各参照を ![[ページ#^id]] で埋め込む
+    Anchor,
+}
+
+pub struct SyncedBlockRef<'a> {
+    pub block_id: String,
+    pub source_id: String,
+    // 参照の場合もオリジナルの内容が入る
+    pub children: &'a [BlockNode],
+}
+
+impl SyncedBlockRef<'_> {
+    pub fn is_original(&self) -> bool {
+        self.block_id == self.source_id
+    }
+}
+
+// ツリー内の同期ブロックを列挙する
+pub fn synced_blocks(tree: &[BlockNode]) -> Vec<SyncedBlockRef<'_>> {
+    let mut synced_blocks = Vec::new();
+    BlockNode::walk(tree, &mut |node| {
+        if let BlockType::SyncedBlock { synced_block } = &node.block.block_type {
+            let block_id = node.id().to_string();
+            let source_id = synced_block
+                .synced_from
+                .as_ref()
+                .map(|SyncedFrom::BlockId { block_id }| block_id.clone())
+                .unwrap_or_else(|| block_id.clone());
+            synced_blocks.push(SyncedBlockRef {
+                block_id,
+                source_id,
+                children: &node.children,
+            });
+        }
+    });
+    synced_blocks
+}
+
+pub fn note_name(source_id: &str) -> String {
+    format!("Synced {}", compact_id(source_id))
+}
+
+pub fn embed(note: &str, anchor: Option<&str>) -> String {
+    match anchor {
+        Some(anchor) => format!("![[{}#^{}]]", note, anchor),
+        None => format!("![[{}]]", note),
+    }
+}
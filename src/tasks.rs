@@ -0,0 +1,148 @@
+use notion_client::objects::{
+    page::{Page, PageProperty},
+    rich_text::{Mention, RichText},
+};
+
+use crate::{
+    blocks,
+    dates::{self, DateStyle},
+    renderer::{render_rich_text, RenderOptions},
+    users::UserDirectory,
+};
+
+// タスクデータベースの各行を1行のタスクとしてプロジェクトノートに書き出す設定
+#[derive(Debug, Clone)]
+pub struct TaskDatabase {
+    pub database_id: String,
+    pub note_title: String,
+    pub due_property: String,
+    pub priority_property: String,
+    pub done_property: String,
+    pub done_date_property: String,
+    pub assignee_property: String,
+}
+
+impl TaskDatabase {
+    pub fn new(database_id: String, note_title: String) -> Self {
+        Self {
+            database_id,
+            note_title,
+            due_property: "期限".to_string(),
+            priority_property: "優先度".to_string(),
+            done_property: "完了".to_string(),
+            done_date_property: "完了日".to_string(),
+            assignee_property: "担当者".to_string(),
+        }
+    }
+}
+
+// Tasksプラグインの優先度
+pub fn priority_emoji(value: &str) -> Option<&'static str> {
+    match value.to_lowercase().as_str() {
+        "highest" | "最高" => Some("🔺"),
+        "high" | "高" => Some("⏫"),
+        "medium" | "中" => Some("🔼"),
+        "low" | "低" => Some("🔽"),
+        "lowest" | "最低" => Some("⏬"),
+        _ => None,
+    }
+}
+
+// チェックボックスの本文から日付メンションを取り除き、Tasksプラグインの期日として返す
+// Notionのブロックには完了日時がないため、完了日（✅）は付けない
+pub fn todo_line(rich_text: &[RichText], options: &RenderOptions) -> (String, Vec<String>) {
+    let mut description = Vec::new();
+    let mut metadata = Vec::new();
+
+    for rt in rich_text {
+        match rt {
+            RichText::Mention {
+                mention: Mention::Date { date },
+                ..
+            } => {
+                if let Some(due) = dates::render_mention(date, &DateStyle::TasksDue) {
+                    metadata.push(due);
+                }
+            }
+            _ => description.push(rt.clone()),
+        }
+    }
+
+    let description = render_rich_text(&description, options).trim().to_string();
+    (description, metadata)
+}
+
+fn property<'a>(page: &'a Page, name: &str) -> Option<&'a PageProperty> {
+    page.properties.get(name)
+}
+
+fn select_name(prop: &PageProperty) -> Option<&str> {
+    match prop {
+        PageProperty::Select {
+            select: Some(select),
+            ..
+        } => select.name.as_deref(),
+        PageProperty::Status {
+            status: Some(status),
+            ..
+        } => status.name.as_deref(),
+        _ => None,
+    }
+}
+
+fn is_done(prop: Option<&PageProperty>) -> bool {
+    match prop {
+        Some(PageProperty::Checkbox { checkbox, .. }) => *checkbox,
+        Some(prop) => matches!(select_name(prop), Some("完了") | Some("Done")),
+        None => false,
+    }
+}
+
+// タスクデータベースの1行を「- [ ] タイトル [[担当者]] ⏫ 📅 期日」に変換する
+pub fn task_entry(
+    page: &Page,
+    title: &str,
+    config: &TaskDatabase,
+    user_directory: Option<&UserDirectory>,
+) -> String {
+    let done = is_done(property(page, &config.done_property));
+    let mut line = format!("- [{}] {}", if done { "x" } else { " " }, title);
+
+    if let Some(PageProperty::People { people, .. }) = property(page, &config.assignee_property) {
+        for user in people {
+            let assignee = match user_directory {
+                Some(user_directory) => user_directory.link(user),
+                None => blocks::user_name(user).unwrap_or_else(|| blocks::user_id(user)),
+            };
+            line.push_str(&format!(" {}", assignee));
+        }
+    }
+
+    if let Some(priority) = property(page, &config.priority_property)
+        .and_then(select_name)
+        .and_then(priority_emoji)
+    {
+        line.push_str(&format!(" {}", priority));
+    }
+
+    if let Some(PageProperty::Date {
+        date: Some(date), ..
+    }) = property(page, &config.due_property)
+    {
+        if let Some(due) = dates::render(date, &DateStyle::TasksDue) {
+            line.push_str(&format!(" {}", due));
+        }
+    }
+
+    // 完了日は完了日のプロパティがある場合のみ（最終更新日時は完了後の編集でも変わるため使わない）
+    if let Some(PageProperty::Date {
+        date: Some(date), ..
+    }) = property(page, &config.done_date_property)
+    {
+        if let Some(day) = dates::start_date(date).filter(|_| done) {
+            line.push_str(&format!(" ✅ {}", day.format("%Y-%m-%d")));
+        }
+    }
+
+    line
+}
@@ -0,0 +1,369 @@
+use notion_client::objects::{block::Block, rich_text::RichText};
+use serde_json::Value;
+
+use crate::{
+    anchors,
+    blocks::{self, compact_id, BlockNode},
+    converter::sanitize_filename,
+    media::{self, MediaIndex},
+    renderer::{
+        block_kind, block_value, caption, file_url, indent, prefix_lines, render_rich_text,
+        rich_text_to_html, MarkdownRenderer, RenderOptions,
+    },
+    tasks,
+};
+
+pub struct RenderContext<'a> {
+    renderer: &'a MarkdownRenderer,
+    // 番号付きリストの何番目の項目か（それ以外のブロックでは0）
+    pub list_number: usize,
+    pub options: &'a RenderOptions,
+}
+
+impl<'a> RenderContext<'a> {
+    pub fn new(
+        renderer: &'a MarkdownRenderer,
+        list_number: usize,
+        options: &'a RenderOptions,
+    ) -> Self {
+        Self {
+            renderer,
+            list_number,
+            options,
+        }
+    }
+
+    pub fn render_children(&self, node: &BlockNode) -> String {
+        self.renderer.render_with(&node.children, self.options)
+    }
+
+    pub fn rich_text(&self, rich_text: &[RichText]) -> String {
+        render_rich_text(rich_text, self.options)
+    }
+
+    // 参照されているブロックに付ける ^id
+    pub fn anchor(&self, node: &BlockNode) -> Option<String> {
+        self.options
+            .anchors
+            .contains(&compact_id(node.id()))
+            .then(|| anchors::anchor(node.id()))
+    }
+}
+
+pub trait BlockRenderer: Send + Sync {
+    fn render(&self, node: &BlockNode, context: &RenderContext) -> String;
+}
+
+pub struct DefaultBlockRenderer;
+
+impl BlockRenderer for DefaultBlockRenderer {
+    fn render(&self, node: &BlockNode, context: &RenderContext) -> String {
+        let block = &node.block;
+        let kind = block_kind(block);
+        let value = block_value(block);
+        let (text, metadata) = match blocks::rich_text(block) {
+            Some(rich_text) if kind == "to_do" && context.options.tasks_mode => {
+                tasks::todo_line(rich_text, context.options)
+            }
+            Some(rich_text) => (context.rich_text(rich_text), Vec::new()),
+            None => (String::new(), Vec::new()),
+        };
+        let children = context.render_children(node);
+
+        // コメントの脚注は本文の末尾、Tasksプラグインの期日はその後ろに付ける
+        let text = match context.options.footnotes.get(&compact_id(node.id())) {
+            Some(label) => format!("{} [^{}]", text, label),
+            None => text,
+        };
+        let text = if metadata.is_empty() {
+            text
+        } else {
+            format!("{} {}", text, metadata.join(" "))
+        };
+
+        // 本文の行末に付けられないブロックは、ブロックの後の行にアンカーを付ける
+        // 見出しは [[ページ#見出し]] でリンクするため付けない
+        let anchor = context.anchor(node);
+        let (text, anchor) = match (kind.as_str(), anchor) {
+            ("heading_1" | "heading_2" | "heading_3", _) => (text, None),
+            (
+                "paragraph" | "bulleted_list_item" | "numbered_list_item" | "to_do" | "toggle",
+                Some(anchor),
+            ) => (format!("{} {}", text, anchor), None),
+            (_, anchor) => (text, anchor),
+        };
+
+        let rendered = match kind.as_str() {
+            "paragraph" => join_blocks(&text, &children),
+            "heading_1" => join_blocks(&heading(1, &text), &children),
+            "heading_2" => join_blocks(&heading(2, &text), &children),
+            "heading_3" => join_blocks(&heading(3, &text), &children),
+            "bulleted_list_item" | "toggle" => list_item("- ", &text, &children),
+            "numbered_list_item" => {
+                list_item(&format!("{}. ", context.list_number), &text, &children)
+            }
+            "to_do" => {
+                let checked = value.get("checked").and_then(Value::as_bool) == Some(true);
+                let text = format!("[{}] {}", if checked { "x" } else { " " }, text);
+                list_item("- ", &text, &children)
+            }
+            "quote" => prefix_lines(&join_lines(&text, &children), "> "),
+            "callout" => callout(&text, &children),
+            "code" => code(&value, block),
+            "divider" => "---".to_string(),
+            "equation" => format!(
+                "$$\n{}\n$$",
+                value
+                    .get("expression")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+            ),
+            "image" => match file_url(&value) {
+                Some(url) => format!("![{}]({})", caption(&value), url),
+                None => String::new(),
+            },
+            "video" => match file_url(&value) {
+                Some(url) => video(&url, &context.options.media),
+                None => String::new(),
+            },
+            "audio" | "pdf" | "file" => match file_url(&value) {
+                Some(url) => match context.options.media.local_file(&url) {
+                    Some(file) if kind == "file" => format!("[[{}]]", file),
+                    Some(file) => format!("![[{}]]", file),
+                    None => media::link(&caption(&value), &url),
+                },
+                None => String::new(),
+            },
+            "embed" => match file_url(&value) {
+                Some(url) => media::render_embed(
+                    &url,
+                    &link_label(&value, &url, context),
+                    &context.options.media,
+                ),
+                None => String::new(),
+            },
+            "bookmark" | "link_preview" => match file_url(&value) {
+                Some(url) => media::link(&link_label(&value, &url, context), &url),
+                None => String::new(),
+            },
+            "table" => table(node, context),
+            "child_page" | "child_database" => match value.get("title").and_then(Value::as_str) {
+                Some(title) => format!("[[{}]]", sanitize_filename(title)),
+                None => String::new(),
+            },
+            "synced_block" => match context.options.synced_embeds.get(&compact_id(node.id())) {
+                Some(embed) => embed.clone(),
+                // 単独の行の ^id は直前の1ブロックにしか付かないため、複数のブロックはコールアウトにまとめる
+                None if anchor.is_some() && node.children.len() > 1 => callout("", &children),
+                None => children,
+            },
+            "column_list" | "column" => children,
+            _ => String::new(),
+        };
+
+        match anchor {
+            Some(anchor) if !rendered.trim().is_empty() => format!("{}\n\n{}", rendered, anchor),
+            _ => rendered,
+        }
+    }
+}
+
+fn video(url: &str, media: &MediaIndex) -> String {
+    if let Some(player) = media::player(url) {
+        return player;
+    }
+    match media.local_file(url) {
+        Some(file) => format!("![[{}]]", file),
+        None => format!("![]({})", url),
+    }
+}
+
+// キャプションがなければ取得済みのページタイトルを使う
+fn link_label(value: &Value, url: &str, context: &RenderContext) -> String {
+    let label = caption(value);
+    if label.is_empty() {
+        context
+            .options
+            .media
+            .title(url)
+            .unwrap_or_default()
+            .to_string()
+    } else {
+        label
+    }
+}
+
+fn join_blocks(text: &str, children: &str) -> String {
+    match (text.is_empty(), children.is_empty()) {
+        (_, true) => text.to_string(),
+        (true, false) => children.to_string(),
+        (false, false) => format!("{}\n\n{}", text, children),
+    }
+}
+
+fn join_lines(text: &str, children: &str) -> String {
+    if children.is_empty() {
+        text.to_string()
+    } else {
+        format!("{}\n{}", text, children)
+    }
+}
+
+fn heading(level: usize, text: &str) -> String {
+    format!("{} {}", "#".repeat(level), text.replace('\n', " "))
+}
+
+// 2行目以降と子ブロックはリストマーカーの幅だけインデントする
+fn list_item(marker: &str, text: &str, children: &str) -> String {
+    let width = marker.len();
+    let mut lines = text.lines();
+    let mut item = format!("{}{}", marker, lines.next().unwrap_or_default());
+
+    let rest = lines.collect::<Vec<_>>().join("\n");
+    if !rest.is_empty() {
+        item.push('\n');
+        item.push_str(&indent(&rest, width));
+    }
+    if !children.is_empty() {
+        item.push('\n');
+        item.push_str(&indent(children, width));
+    }
+    item
+}
+
+fn callout(text: &str, children: &str) -> String {
+    let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+    let mut callout = format!("> [!note] {}", first).trim_end().to_string();
+
+    let body = join_lines(rest, children);
+    let body = body.trim_start_matches('\n');
+    if !body.is_empty() {
+        callout.push('\n');
+        callout.push_str(&prefix_lines(body, "> "));
+    }
+    callout
+}
+
+fn code(value: &Value, block: &Block) -> String {
+    let language = match value.get("language").and_then(Value::as_str) {
+        Some("plain text") | None => "",
+        Some(language) => language,
+    };
+    let source = blocks::rich_text(block)
+        .map(blocks::plain_text)
+        .unwrap_or_default();
+    format!("```{}\n{}\n```", language, source)
+}
+
+fn table(node: &BlockNode, context: &RenderContext) -> String {
+    let value = block_value(&node.block);
+    let has_column_header = value.get("has_column_header").and_then(Value::as_bool) == Some(true);
+    let has_row_header = value.get("has_row_header").and_then(Value::as_bool) == Some(true);
+
+    let rows: Vec<Vec<Vec<RichText>>> = node
+        .children
+        .iter()
+        .filter_map(|row| {
+            let cells = block_value(&row.block).get("cells")?.clone();
+            serde_json::from_value(cells).ok()
+        })
+        .collect();
+    let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+    if width == 0 {
+        return String::new();
+    }
+
+    // コード内の改行はMarkdownの表で表現できないためHTMLにする
+    if rows.iter().flatten().any(|cell| has_code_newline(cell)) {
+        return html_table(&rows, width, has_column_header, has_row_header);
+    }
+
+    let render_row = |row: &Vec<Vec<RichText>>| {
+        let cells: Vec<String> = (0..width)
+            .map(|i| {
+                let cell = row
+                    .get(i)
+                    .map(|cell| table_cell(cell, context))
+                    .unwrap_or_default();
+                if i == 0 && has_row_header && !cell.is_empty() {
+                    format!("**{}**", cell)
+                } else {
+                    cell
+                }
+            })
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    // Markdownの表は見出し行が必須のため、見出しのない表は空の見出し行を付ける
+    let (header, body) = if has_column_header {
+        (render_row(&rows[0]), &rows[1..])
+    } else {
+        (format!("|{}", " |".repeat(width)), &rows[..])
+    };
+    let mut lines = vec![header, format!("| {} |", vec!["---"; width].join(" | "))];
+    lines.extend(body.iter().map(render_row));
+    lines.join("\n")
+}
+
+// 列の区切りと解釈されないようにパイプをエスケープし、改行は<br>にする
+fn table_cell(cell: &[RichText], context: &RenderContext) -> String {
+    context
+        .rich_text(cell)
+        .trim()
+        .replace('|', "\\|")
+        .replace('\n', "<br>")
+}
+
+fn has_code_newline(cell: &[RichText]) -> bool {
+    cell.iter().any(|rt| {
+        let code = matches!(
+            rt,
+            RichText::Text {
+                annotations: Some(annotations),
+                ..
+            } if annotations.code
+        );
+        code && rt.plain_text().unwrap_or_default().contains('\n')
+    })
+}
+
+fn html_table(
+    rows: &[Vec<Vec<RichText>>],
+    width: usize,
+    has_column_header: bool,
+    has_row_header: bool,
+) -> String {
+    let render_row = |row: &Vec<Vec<RichText>>, header: bool| {
+        let cells: String = (0..width)
+            .map(|i| {
+                let cell = row
+                    .get(i)
+                    .map(|cell| rich_text_to_html(cell))
+                    .unwrap_or_default();
+                let tag = if header || (i == 0 && has_row_header) {
+                    "th"
+                } else {
+                    "td"
+                };
+                format!("<{tag}>{}</{tag}>", cell.trim())
+            })
+            .collect();
+        format!("<tr>{}</tr>", cells)
+    };
+
+    let mut lines = vec!["<table>".to_string()];
+    let body = if has_column_header {
+        lines.push("<thead>".to_string());
+        lines.push(render_row(&rows[0], true));
+        lines.push("</thead>".to_string());
+        &rows[1..]
+    } else {
+        rows
+    };
+    lines.push("<tbody>".to_string());
+    lines.extend(body.iter().map(|row| render_row(row, false)));
+    lines.push("</tbody>".to_string());
+    lines.push("</table>".to_string());
+    lines.join("\n")
+}
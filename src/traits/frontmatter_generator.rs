@@ -1,6 +1,15 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
 
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use log::warn;
 use notion_client::{
     endpoints::{
         databases::query::request::{QueryDatabaseRequest, Sort, SortDirection},
@@ -9,30 +18,111 @@ use notion_client::{
     objects::page::{Page, PageProperty},
 };
 
-use crate::NotionToObsidianError;
+use crate::{
+    converter::extract_page_title,
+    dates::{self, DateStyle},
+    i18n::Msg,
+    media::{MediaOptions, PageAssets},
+    properties,
+    relations::{PageTitleCache, RelationResolver},
+    tr,
+    users::UserDirectory,
+    NotionToObsidianError,
+};
 
+#[async_trait]
 pub trait FrontmatterGenerator: Send + Sync {
-    fn generate(&self, page: &Page, client: &Client) -> Result<String, NotionToObsidianError>;
+    async fn generate(&self, page: &Page, client: &Client)
+        -> Result<String, NotionToObsidianError>;
+
+    // 一括変換の最後に呼ぶ（キャッシュの保存など）
+    fn finish(&self) -> Result<(), NotionToObsidianError> {
+        Ok(())
+    }
 }
 
-pub struct DefaultFrontmatterGenerator;
+// DefaultFrontmatterGeneratorが出力するキー（デフォルトではすべて出力する）
+#[derive(Debug, Clone)]
+pub struct FrontmatterKeys {
+    pub title: bool,
+    // Notion上のタイトル（ファイル名に使えない文字を含む場合もリンクできるように）
+    pub aliases: bool,
+    // ノートの名前を変更しても元のページを特定できるようにする
+    pub notion_id: bool,
+    pub notion_url: bool,
+    pub created: bool,
+    pub updated: bool,
+}
 
-impl FrontmatterGenerator for DefaultFrontmatterGenerator {
-    fn generate(&self, page: &Page, _client: &Client) -> Result<String, NotionToObsidianError> {
-        // if let Some(PageProperty::Title { id, title }) = page.properties.get("名前") {
-        //     if !title.is_empty() {
-        //         Ok(format!("---\ntitle: {}\n---\n", title))
-        //     } else {
-        //         Err(NotionToObsidianError::NoTitleError)
-        //     }
-        // } else {
-        //     Err(NotionToObsidianError::NoTitleError)
-        // }
+impl Default for FrontmatterKeys {
+    fn default() -> Self {
+        Self {
+            title: true,
+            aliases: true,
+            notion_id: true,
+            notion_url: true,
+            created: true,
+            updated: true,
+        }
+    }
+}
 
+#[derive(Default)]
+pub struct DefaultFrontmatterGenerator {
+    keys: FrontmatterKeys,
+    // page_assetsがtrueの場合にアイコン・カバー画像を出力する
+    media: MediaOptions,
+}
+
+impl DefaultFrontmatterGenerator {
+    pub fn with_keys(self, keys: FrontmatterKeys) -> DefaultFrontmatterGenerator {
+        DefaultFrontmatterGenerator { keys, ..self }
+    }
+
+    // page_assetsを指定した場合はアイコンとカバー画像をicon・bannerとして出力する
+    // 変換に指定するMediaOptionsと同じものを渡す（ダウンロードしたファイルを参照するため）
+    pub fn with_media_options(self, media: MediaOptions) -> DefaultFrontmatterGenerator {
+        DefaultFrontmatterGenerator { media, ..self }
+    }
+}
+
+#[async_trait]
+impl FrontmatterGenerator for DefaultFrontmatterGenerator {
+    async fn generate(
+        &self,
+        page: &Page,
+        _client: &Client,
+    ) -> Result<String, NotionToObsidianError> {
         let mut frontmatter = String::from("---\n");
+        let title = extract_page_title(page);
 
-        let formatted_time = format_datetime(page.created_time);
-        frontmatter.push_str(&format!("created: {}\n", formatted_time));
+        if let Some(title) = title.as_ref().filter(|_| self.keys.title) {
+            frontmatter.push_str(&format!("title: {}\n", yaml_string(title)));
+        }
+        if let Some(title) = title.as_ref().filter(|_| self.keys.aliases) {
+            frontmatter.push_str(&format!("aliases:\n  - {}\n", yaml_string(title)));
+        }
+        if self.keys.notion_id {
+            frontmatter.push_str(&format!("notion_id: {}\n", page.id));
+        }
+        if self.keys.notion_url {
+            frontmatter.push_str(&format!("notion_url: {}\n", page.url));
+        }
+        if self.keys.created {
+            let formatted_time = format_datetime(page.created_time);
+            frontmatter.push_str(&format!("created: {}\n", formatted_time));
+        }
+        if self.keys.updated {
+            let formatted_time = format_datetime(page.last_edited_time);
+            frontmatter.push_str(&format!("updated: {}\n", formatted_time));
+        }
+
+        if self.media.page_assets {
+            frontmatter.push_str(
+                &PageAssets::from_page(page, self.media.attachments_folder.as_deref())
+                    .frontmatter(),
+            );
+        }
 
         frontmatter.push_str("---\n");
 
@@ -41,71 +131,176 @@ impl FrontmatterGenerator for DefaultFrontmatterGenerator {
 }
 
 pub struct MyFrontmatterGenerator {
-    tag_mapping: HashMap<String, String>,
+    // タグのページIDからタグ名（タグデータベースにないページは必要になったときに取得する）
+    tag_titles: Arc<PageTitleCache>,
+    tag_database_id: String,
+    // タグの再読み込みでも見つからなかったID（再読み込みを繰り返さないように記録する）
+    unresolved: Mutex<HashSet<String>>,
+    // タグの再読み込みは実行中に1回だけ行う
+    refreshed: AtomicBool,
+    // 指定した場合はtypesの代わりにすべてのリレーションプロパティを出力する
+    relation_resolver: Option<RelationResolver>,
+    user_directory: Option<Arc<UserDirectory>>,
+    date_style: Option<DateStyle>,
+    // page_assetsがtrueの場合にアイコン・カバー画像を出力する
+    media: MediaOptions,
 }
 
 impl MyFrontmatterGenerator {
-    pub async fn new(tag_database_id: &str, token: String) -> MyFrontmatterGenerator {
-        let client = Client::new(token, None).unwrap();
-        let tag_mapping = Self::load_tags(tag_database_id, &client).await.unwrap();
-        MyFrontmatterGenerator { tag_mapping }
+    pub async fn new(
+        tag_database_id: &str,
+        token: String,
+    ) -> Result<MyFrontmatterGenerator, NotionToObsidianError> {
+        let client = Client::new(token, None).map_err(NotionToObsidianError::api)?;
+        let tag_titles = PageTitleCache::new();
+        for (id, name) in Self::load_tags(tag_database_id, &client).await? {
+            tag_titles.insert(&id, name);
+        }
+        Ok(MyFrontmatterGenerator {
+            tag_titles: Arc::new(tag_titles),
+            tag_database_id: tag_database_id.to_string(),
+            unresolved: Mutex::new(HashSet::new()),
+            refreshed: AtomicBool::new(false),
+            relation_resolver: None,
+            user_directory: None,
+            date_style: None,
+            media: MediaOptions::default(),
+        })
+    }
+
+    // 他のジェネレーターや処理とタイトルのキャッシュを共有する
+    pub fn title_cache(&self) -> Arc<PageTitleCache> {
+        self.tag_titles.clone()
+    }
+
+    pub fn with_user_directory(self, user_directory: Arc<UserDirectory>) -> MyFrontmatterGenerator {
+        MyFrontmatterGenerator {
+            user_directory: Some(user_directory),
+            ..self
+        }
+    }
+
+    pub fn with_date_style(self, date_style: DateStyle) -> MyFrontmatterGenerator {
+        MyFrontmatterGenerator {
+            date_style: Some(date_style),
+            ..self
+        }
+    }
+
+    // タグのリレーションもtypesとして出力する場合は with_key(<プロパティ名>, "types") を指定する
+    pub fn with_relation_resolver(
+        self,
+        relation_resolver: RelationResolver,
+    ) -> MyFrontmatterGenerator {
+        MyFrontmatterGenerator {
+            relation_resolver: Some(relation_resolver),
+            ..self
+        }
+    }
+
+    // page_assetsを指定した場合はアイコンとカバー画像をicon・bannerとして出力する
+    pub fn with_media_options(self, media: MediaOptions) -> MyFrontmatterGenerator {
+        MyFrontmatterGenerator { media, ..self }
     }
 
     pub async fn load_tags(
         tag_database_id: &str,
         client: &Client,
     ) -> Result<HashMap<String, String>, NotionToObsidianError> {
-        let mut tag_mapping = HashMap::new();
-
-        let mut request = QueryDatabaseRequest::default();
-        request.sorts = Some(vec![Sort::Property {
-            property: "名前".to_string(),
-            direction: SortDirection::Ascending,
-        }]);
-
-        let response = client
-            .databases
-            .query_a_database(tag_database_id, request)
-            .await
-            .map_err(|e| NotionToObsidianError::ConversionError(e.to_string()))?;
-
-        for page in response.results {
-            if let Some(PageProperty::Title { title, .. }) = page.properties.get("名前") {
-                if !title.is_empty() {
-                    if let Some(tag_name) = title[0].plain_text() {
-                        tag_mapping.insert(page.id.to_string(), tag_name);
-                    }
+        collect_tags(|cursor| async move {
+            let request = QueryDatabaseRequest {
+                sorts: Some(vec![Sort::Property {
+                    property: properties::TAG_NAME.to_string(),
+                    direction: SortDirection::Ascending,
+                }]),
+                start_cursor: cursor,
+                ..Default::default()
+            };
+
+            let response = client
+                .databases
+                .query_a_database(tag_database_id, request)
+                .await
+                .map_err(|e| NotionToObsidianError::database_query(tag_database_id, e))?;
+            let next_cursor = response.next_cursor.filter(|_| response.has_more);
+            Ok((response.results, next_cursor))
+        })
+        .await
+    }
+
+    // 実行中に追加されたタグを取り込むため、未知のIDがあれば最初の1回だけタグを読み込み直す
+    async fn refresh_tags(&self, client: &Client) {
+        if self.refreshed.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        match Self::load_tags(&self.tag_database_id, client).await {
+            Ok(tags) => {
+                for (id, name) in tags {
+                    self.tag_titles.insert(&id, name);
                 }
             }
+            Err(e) => warn!("{}", tr!(Msg::TagReloadFailed, e)),
         }
+    }
 
-        Ok(tag_mapping)
+    async fn resolve_type(&self, client: &Client, id: &str) -> Option<String> {
+        if let Some(name) = self.tag_titles.get(id) {
+            return Some(name);
+        }
+        if self.unresolved.lock().unwrap().contains(id) {
+            return None;
+        }
+
+        self.refresh_tags(client).await;
+        if let Some(name) = self.tag_titles.get(id) {
+            return Some(name);
+        }
+
+        // タグデータベース以外のページも取得を試みる
+        match self.tag_titles.resolve(client, id).await {
+            Ok(Some(name)) => return Some(name),
+            Ok(None) => warn!("{}", tr!(Msg::RelationPageNoTitle, id)),
+            Err(e) => warn!("{}", tr!(Msg::RelationPageFetchFailed, id, e)),
+        }
+        self.unresolved.lock().unwrap().insert(id.to_string());
+        None
     }
 
-    fn extract_types(&self, page: &Page) -> Option<Vec<String>> {
-        for (_, prop) in &page.properties {
+    async fn extract_types(&self, page: &Page, client: &Client) -> Option<Vec<String>> {
+        for prop in page.properties.values() {
             if let PageProperty::Relation { relation, .. } = prop {
-                return Some(
-                    relation
-                        .iter()
-                        .filter_map(|r| self.tag_mapping.get(&r.id).cloned())
-                        .collect(),
-                );
+                let mut types = Vec::new();
+                for r in relation {
+                    if let Some(name) = self.resolve_type(client, &r.id).await {
+                        types.push(name);
+                    }
+                }
+                return Some(types);
             }
         }
         None
     }
 }
 
+#[async_trait]
 impl FrontmatterGenerator for MyFrontmatterGenerator {
-    fn generate(&self, page: &Page, _client: &Client) -> Result<String, NotionToObsidianError> {
+    async fn generate(
+        &self,
+        page: &Page,
+        client: &Client,
+    ) -> Result<String, NotionToObsidianError> {
         let mut frontmatter = String::from("---\n");
 
-        // タイプ（タグ）の処理
-        frontmatter.push_str("types:\n");
-        if let Some(types) = self.extract_types(page) {
-            for type_name in types {
-                frontmatter.push_str(&format!("  - \"[[{}]]\"\n", type_name));
+        // タイプ（タグ）・リレーションの処理
+        match &self.relation_resolver {
+            Some(resolver) => frontmatter.push_str(&resolver.frontmatter(page, client).await),
+            None => {
+                frontmatter.push_str("types:\n");
+                if let Some(types) = self.extract_types(page, client).await {
+                    for type_name in types {
+                        frontmatter.push_str(&format!("  - \"[[{}]]\"\n", type_name));
+                    }
+                }
             }
         }
 
@@ -114,6 +309,27 @@ impl FrontmatterGenerator for MyFrontmatterGenerator {
             frontmatter.push_str(&format!("URL: {}\n", url));
         }
 
+        // ユーザー（People）の処理
+        if let Some(user_directory) = &self.user_directory {
+            frontmatter.push_str(&user_directory.people_frontmatter(page));
+        }
+
+        // 日付プロパティの処理
+        if let Some(date_style) = &self.date_style {
+            frontmatter.push_str(&dates::frontmatter(page, date_style));
+        }
+
+        // アイコン・カバー画像の処理
+        if self.media.page_assets {
+            frontmatter.push_str(
+                &PageAssets::from_page(page, self.media.attachments_folder.as_deref())
+                    .frontmatter(),
+            );
+        }
+
+        // ノートの名前を変更しても元のページを特定できるようにする
+        frontmatter.push_str(&format!("notion_id: {}\n", page.id));
+
         // 作成日時の処理
         let formatted_time = format_datetime(page.created_time);
         frontmatter.push_str(&format!("created: {}\n", formatted_time));
@@ -121,16 +337,61 @@ impl FrontmatterGenerator for MyFrontmatterGenerator {
         frontmatter.push_str("---\n");
         Ok(frontmatter)
     }
+
+    fn finish(&self) -> Result<(), NotionToObsidianError> {
+        match &self.relation_resolver {
+            Some(resolver) => resolver.save(),
+            None => Ok(()),
+        }
+    }
+}
+
+// タグデータベースをページ送りしながら読み込み、ページIDからタグ名への対応を返す
+// queryはカーソルを受け取り、結果のページと次のカーソル（最後の場合はNone）を返す
+pub async fn collect_tags<F, Fut>(
+    mut query: F,
+) -> Result<HashMap<String, String>, NotionToObsidianError>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<(Vec<Page>, Option<String>), NotionToObsidianError>>,
+{
+    let mut tag_mapping = HashMap::new();
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let (pages, next_cursor) = query(cursor).await?;
+        for page in pages {
+            if let Some(PageProperty::Title { title, .. }) =
+                page.properties.get(properties::TAG_NAME)
+            {
+                if let Some(tag_name) = title.first().and_then(|text| text.plain_text()) {
+                    tag_mapping.insert(page.id.to_string(), tag_name);
+                }
+            }
+        }
+
+        match next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    Ok(tag_mapping)
+}
+
+// ダブルクォートで囲んだYAMLの文字列
+pub(crate) fn yaml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
 }
 
-fn format_datetime(dt: DateTime<Utc>) -> String {
+pub(crate) fn format_datetime(dt: DateTime<Utc>) -> String {
     dt.with_timezone(&chrono::Local)
         .format("%Y-%m-%d %H:%M")
         .to_string()
 }
 
 fn extract_url(page: &Page) -> Option<String> {
-    for (_, prop) in &page.properties {
+    for prop in page.properties.values() {
         if let PageProperty::Url { url, .. } = prop {
             return url.clone();
         }
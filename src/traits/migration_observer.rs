@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use crate::migration::MigrationReport;
+
+// 一括変換の進捗イベント
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationEvent {
+    // 変換対象のページを取得した
+    PagesDiscovered {
+        count: usize,
+    },
+    PageStarted {
+        page_id: String,
+        title: String,
+    },
+    // ページのブロックをすべて取得した（countは子孫を含むブロック数）
+    BlocksFetched {
+        page_id: String,
+        count: usize,
+    },
+    AssetDownloaded {
+        page_id: String,
+        url: String,
+        path: PathBuf,
+    },
+    PageWritten {
+        page_id: String,
+        title: String,
+        path: PathBuf,
+    },
+    // 変換または保存に失敗した
+    PageFailed {
+        page_id: String,
+        title: String,
+        error: String,
+    },
+    // ノートは保存したが後処理に失敗した（次回の実行で再試行する）
+    PostProcessFailed {
+        page_id: String,
+        title: String,
+        error: String,
+    },
+    RunFinished {
+        report: MigrationReport,
+    },
+}
+
+// 進捗の表示やログの出力は利用側でこのトレイトを実装して行う
+pub trait MigrationObserver: Send + Sync {
+    fn on_event(&self, event: &MigrationEvent);
+}
+
+// 何も出力しない（デフォルト）
+pub struct NoopObserver;
+
+impl MigrationObserver for NoopObserver {
+    fn on_event(&self, _event: &MigrationEvent) {}
+}
@@ -1,9 +1,15 @@
+pub mod block_renderer;
 pub mod frontmatter_generator;
+pub mod migration_observer;
 pub mod page_provider;
 pub mod post_processor;
 
+pub use block_renderer::{BlockRenderer, DefaultBlockRenderer, RenderContext};
 pub use frontmatter_generator::{
-    DefaultFrontmatterGenerator, FrontmatterGenerator, MyFrontmatterGenerator,
+    DefaultFrontmatterGenerator, FrontmatterGenerator, FrontmatterKeys, MyFrontmatterGenerator,
 };
-pub use page_provider::{DatabasePageProvider, PageProvider, SinglePageProvider};
-pub use post_processor::PostProcessor;
+pub use migration_observer::{MigrationEvent, MigrationObserver, NoopObserver};
+pub use page_provider::{
+    DatabasePageProvider, ExportPageProvider, PageProvider, SinglePageProvider,
+};
+pub use post_processor::{PostProcessor, PostProcessorChain};
@@ -1,4 +1,10 @@
-use crate::error::Result;
+use std::sync::Arc;
+
+use crate::{
+    error::{NotionToObsidianError, Result},
+    export::{ExportedPage, NotionExport},
+    properties,
+};
 use async_trait::async_trait;
 use notion_client::{
     endpoints::{
@@ -30,12 +36,12 @@ impl DatabasePageProvider {
         QueryDatabaseRequest {
             filter: Some(Filter::Value {
                 filter_type: FilterType::Property {
-                    property: "移行済み".to_string(),
+                    property: properties::MIGRATED.to_string(),
                     condition: PropertyCondition::Checkbox(CheckBoxCondition::Equals(false)),
                 },
             }),
             sorts: Some(vec![Sort::Property {
-                property: "作成日時".to_string(),
+                property: properties::CREATED_TIME.to_string(),
                 direction: SortDirection::Descending,
             }]),
             page_size: Some(self.limit.try_into().unwrap()),
@@ -52,7 +58,7 @@ impl PageProvider for DatabasePageProvider {
             .databases
             .query_a_database(&self.database_id, request)
             .await
-            .map_err(|e| crate::error::NotionToObsidianError::ConversionError(e.to_string()))?;
+            .map_err(|e| NotionToObsidianError::database_query(&self.database_id, e))?;
 
         Ok(response.results)
     }
@@ -75,8 +81,30 @@ impl PageProvider for SinglePageProvider {
             .pages
             .retrieve_a_page(&self.page_id, None)
             .await
-            .map_err(|e| crate::error::NotionToObsidianError::PageRetrievalError(e.to_string()))?;
+            .map_err(|e| NotionToObsidianError::page_retrieval(&self.page_id, e))?;
 
         Ok(vec![page])
     }
 }
+
+// エクスポートのzipのすべてのページ（APIは呼び出さない）
+pub struct ExportPageProvider {
+    export: Arc<NotionExport>,
+}
+
+impl ExportPageProvider {
+    pub fn new(export: Arc<NotionExport>) -> Self {
+        Self { export }
+    }
+}
+
+#[async_trait]
+impl PageProvider for ExportPageProvider {
+    async fn get_pages(&self, _client: &Client) -> Result<Vec<Page>> {
+        self.export
+            .pages()
+            .iter()
+            .map(ExportedPage::to_page)
+            .collect()
+    }
+}
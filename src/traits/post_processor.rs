@@ -1,15 +1,65 @@
 use async_trait::async_trait;
+use log::{error, info, warn};
 use notion_client::{
-    endpoints::{pages::update::request::UpdatePagePropertiesRequest, Client},
-    objects::page::{Page, PageProperty},
+    endpoints::{
+        comments::create::request::CreateCommentRequest,
+        pages::update::request::UpdatePagePropertiesRequest, Client,
+    },
+    objects::{
+        page::{Page, PageProperty},
+        parent::Parent,
+        rich_text::RichText,
+    },
+};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
 };
-use std::collections::BTreeMap;
 
-use crate::NotionToObsidianError;
+use crate::{i18n::Msg, properties, tr, NotionToObsidianError};
 
 #[async_trait]
 pub trait PostProcessor: Send + Sync {
     async fn process(&self, page: &Page, client: &Client) -> Result<(), NotionToObsidianError>;
+
+    // 保存したノートのパスを使う場合はこちらを実装する
+    async fn process_note(
+        &self,
+        page: &Page,
+        client: &Client,
+        _note: &Path,
+    ) -> Result<(), NotionToObsidianError> {
+        self.process(page, client).await
+    }
+
+    // 完了した処理のstep_idをcompletedに追加する
+    // 再試行ではcompletedに含まれる処理を実行しない（コメントの追加などを繰り返さないため）
+    async fn process_steps(
+        &self,
+        page: &Page,
+        client: &Client,
+        note: &Path,
+        completed: &mut Vec<String>,
+    ) -> Result<(), NotionToObsidianError> {
+        let step = self.step_id();
+        if completed.contains(&step) {
+            return Ok(());
+        }
+        self.process_note(page, client, note).await?;
+        completed.push(step);
+        Ok(())
+    }
+
+    // エラーの報告に使う名前
+    fn name(&self) -> &str {
+        "post_processor"
+    }
+
+    // 再試行で完了済みかを判定する識別子（設定の追加・並べ替えで変わらないよう、名前と設定値から作る）
+    fn step_id(&self) -> String {
+        self.name().to_string()
+    }
 }
 
 pub struct DefaultPostProcessor;
@@ -31,7 +81,7 @@ impl PostProcessor for MyPostProcessor {
             properties: {
                 let mut props = BTreeMap::new();
                 props.insert(
-                    "移行済み".to_string(),
+                    properties::MIGRATED.to_string(),
                     Some(PageProperty::Checkbox {
                         id: None,
                         checkbox: true,
@@ -42,13 +92,323 @@ impl PostProcessor for MyPostProcessor {
             ..Default::default()
         };
 
-        match client.pages.update_page_properties(&page.id, request).await {
-            Ok(_) => {}
-            Err(e) => {
-                eprintln!("Failed to update page properties: {:?}", e);
+        client
+            .pages
+            .update_page_properties(&page.id, request)
+            .await
+            .map_err(|e| NotionToObsidianError::page_update(&page.id, e))?;
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "set_migrated_flag"
+    }
+
+    fn step_id(&self) -> String {
+        format!("{}:{}", self.name(), properties::MIGRATED)
+    }
+}
+
+// 複数の後処理を登録順に実行する
+// 失敗した時点で残りの処理は実行せず、失敗した処理の名前を含むエラーを返す
+#[derive(Default)]
+pub struct PostProcessorChain {
+    processors: Vec<Box<dyn PostProcessor>>,
+}
+
+impl PostProcessorChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, processor: Box<dyn PostProcessor>) -> PostProcessorChain {
+        self.processors.push(processor);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+}
+
+#[async_trait]
+impl PostProcessor for PostProcessorChain {
+    async fn process(&self, page: &Page, client: &Client) -> Result<(), NotionToObsidianError> {
+        for processor in &self.processors {
+            processor
+                .process(page, client)
+                .await
+                .map_err(|e| step_error(processor.name(), e))?;
+        }
+        Ok(())
+    }
+
+    async fn process_note(
+        &self,
+        page: &Page,
+        client: &Client,
+        note: &Path,
+    ) -> Result<(), NotionToObsidianError> {
+        self.process_steps(page, client, note, &mut Vec::new())
+            .await
+    }
+
+    async fn process_steps(
+        &self,
+        page: &Page,
+        client: &Client,
+        note: &Path,
+        completed: &mut Vec<String>,
+    ) -> Result<(), NotionToObsidianError> {
+        for processor in &self.processors {
+            let step = processor.step_id();
+            if completed.contains(&step) {
+                continue;
+            }
+            match processor.process_note(page, client, note).await {
+                Ok(_) => {
+                    info!(
+                        "{}",
+                        tr!(Msg::PostProcessStepDone, processor.name(), page.id)
+                    );
+                    completed.push(step);
+                }
+                Err(e) => {
+                    error!(
+                        "{}",
+                        tr!(Msg::PostProcessStepFailed, processor.name(), page.id, e)
+                    );
+                    return Err(step_error(processor.name(), e));
+                }
             }
         }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "chain"
+    }
+}
+
+// 失敗した処理の名前を付ける（元のエラーはsourceとして残す）
+fn step_error(name: &str, e: NotionToObsidianError) -> NotionToObsidianError {
+    match e {
+        NotionToObsidianError::PostProcessError { .. } => e,
+        e => NotionToObsidianError::post_process(name, e),
+    }
+}
+
+async fn update_property(
+    page: &Page,
+    client: &Client,
+    name: &str,
+    property: PageProperty,
+) -> Result<(), NotionToObsidianError> {
+    let mut properties = BTreeMap::new();
+    properties.insert(name.to_string(), Some(property));
+    let request = UpdatePagePropertiesRequest {
+        properties,
+        ..Default::default()
+    };
+    client
+        .pages
+        .update_page_properties(&page.id, request)
+        .await
+        .map_err(|e| NotionToObsidianError::page_update(&page.id, e))?;
+    Ok(())
+}
+
+// チェックボックスプロパティを設定する
+pub struct SetCheckbox {
+    pub property: String,
+    pub checked: bool,
+}
+
+#[async_trait]
+impl PostProcessor for SetCheckbox {
+    async fn process(&self, page: &Page, client: &Client) -> Result<(), NotionToObsidianError> {
+        let property = PageProperty::Checkbox {
+            id: None,
+            checkbox: self.checked,
+        };
+        update_property(page, client, &self.property, property).await
+    }
+
+    fn name(&self) -> &str {
+        "set_checkbox"
+    }
+
+    fn step_id(&self) -> String {
+        format!("{}:{}={}", self.name(), self.property, self.checked)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionPropertyKind {
+    Select,
+    Status,
+}
+
+// セレクト・ステータスプロパティを指定した選択肢にする
+pub struct SetOption {
+    pub property: String,
+    pub kind: OptionPropertyKind,
+    pub value: String,
+}
+
+#[async_trait]
+impl PostProcessor for SetOption {
+    async fn process(&self, page: &Page, client: &Client) -> Result<(), NotionToObsidianError> {
+        let kind = match self.kind {
+            OptionPropertyKind::Select => "select",
+            OptionPropertyKind::Status => "status",
+        };
+        let property: PageProperty =
+            serde_json::from_value(json!({ "type": kind, kind: { "name": self.value } }))
+                .map_err(|e| NotionToObsidianError::ConversionError(e.to_string()))?;
+        update_property(page, client, &self.property, property).await
+    }
+
+    fn name(&self) -> &str {
+        match self.kind {
+            OptionPropertyKind::Select => "set_select",
+            OptionPropertyKind::Status => "set_status",
+        }
+    }
+
+    fn step_id(&self) -> String {
+        format!("{}:{}={}", self.name(), self.property, self.value)
+    }
+}
+
+// URLプロパティに保存先のノートを書き込む
+// vault_nameを指定した場合はobsidian://のURI、それ以外はバルト内のパス
+pub struct WriteNoteUrl {
+    pub property: String,
+    pub vault_root: PathBuf,
+    pub vault_name: Option<String>,
+}
+
+impl WriteNoteUrl {
+    pub fn note_url(&self, note: &Path) -> String {
+        let relative = note.strip_prefix(&self.vault_root).unwrap_or(note);
+        let path = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        match &self.vault_name {
+            Some(vault) => format!(
+                "obsidian://open?vault={}&file={}",
+                encode_uri_component(vault),
+                encode_uri_component(path.trim_end_matches(".md"))
+            ),
+            None => path,
+        }
+    }
+}
 
+fn encode_uri_component(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl PostProcessor for WriteNoteUrl {
+    // 保存先のノートがない呼び出しでは書き込まない
+    async fn process(&self, page: &Page, _client: &Client) -> Result<(), NotionToObsidianError> {
+        warn!("{}", tr!(Msg::NoteUrlSkipped, page.id));
         Ok(())
     }
+
+    async fn process_note(
+        &self,
+        page: &Page,
+        client: &Client,
+        note: &Path,
+    ) -> Result<(), NotionToObsidianError> {
+        let property = PageProperty::Url {
+            id: None,
+            url: Some(self.note_url(note)),
+        };
+        update_property(page, client, &self.property, property).await
+    }
+
+    fn name(&self) -> &str {
+        "write_note_url"
+    }
+
+    fn step_id(&self) -> String {
+        format!("{}:{}", self.name(), self.property)
+    }
+}
+
+// ページをアーカイブする（チェーンの最後に登録する）
+pub struct ArchivePage;
+
+#[async_trait]
+impl PostProcessor for ArchivePage {
+    async fn process(&self, page: &Page, client: &Client) -> Result<(), NotionToObsidianError> {
+        let request = UpdatePagePropertiesRequest {
+            archived: Some(true),
+            ..Default::default()
+        };
+        client
+            .pages
+            .update_page_properties(&page.id, request)
+            .await
+            .map_err(|e| NotionToObsidianError::page_update(&page.id, e))?;
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "archive"
+    }
+}
+
+// ページに移行済みのコメントを追加する
+pub struct AppendComment {
+    pub text: String,
+}
+
+#[async_trait]
+impl PostProcessor for AppendComment {
+    async fn process(&self, page: &Page, client: &Client) -> Result<(), NotionToObsidianError> {
+        let rich_text: RichText = serde_json::from_value(json!({
+            "type": "text",
+            "text": { "content": self.text, "link": null },
+            "plain_text": self.text,
+            "href": null
+        }))
+        .map_err(|e| NotionToObsidianError::ConversionError(e.to_string()))?;
+        let request = CreateCommentRequest {
+            parent: Some(Parent::PageId {
+                page_id: page.id.clone(),
+            }),
+            rich_text: vec![rich_text],
+            ..Default::default()
+        };
+        client
+            .comments
+            .create_comment(request)
+            .await
+            .map_err(|e| NotionToObsidianError::page_update(&page.id, e))?;
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "append_comment"
+    }
+
+    fn step_id(&self) -> String {
+        format!("{}:{}", self.name(), self.text)
+    }
 }
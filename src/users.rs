@@ -0,0 +1,139 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use notion_client::{
+    endpoints::Client,
+    objects::{
+        page::{Page, PageProperty},
+        rich_text::Mention,
+        user::User,
+    },
+};
+
+use crate::{
+    blocks::{self, BlockNode},
+    converter::sanitize_filename,
+    error::{NotionToObsidianError, Result},
+    traits::frontmatter_generator::yaml_string,
+    vault,
+};
+
+#[derive(Debug, Default)]
+pub struct UserDirectory {
+    names: HashMap<String, String>,
+    stub_folder: Option<String>,
+}
+
+impl UserDirectory {
+    pub async fn new(token: String) -> Result<UserDirectory> {
+        let client = Client::new(token, None).map_err(NotionToObsidianError::api)?;
+        let names = Self::load_users(&client).await?;
+        Ok(UserDirectory {
+            names,
+            stub_folder: None,
+        })
+    }
+
+    // 人物ノートのスタブを作成するフォルダ
+    pub fn with_stub_folder(self, folder: String) -> UserDirectory {
+        UserDirectory {
+            stub_folder: Some(folder),
+            ..self
+        }
+    }
+
+    pub async fn load_users(client: &Client) -> Result<HashMap<String, String>> {
+        let mut names = HashMap::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let response = client
+                .users
+                .list_all_users(cursor.as_deref(), None)
+                .await
+                .map_err(NotionToObsidianError::api)?;
+
+            for user in response.results {
+                if let Some(name) = blocks::user_name(&user) {
+                    names.insert(blocks::user_id(&user), name);
+                }
+            }
+
+            match response.next_cursor {
+                Some(next) if response.has_more => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        Ok(names)
+    }
+
+    pub fn name(&self, user: &User) -> String {
+        let id = blocks::user_id(user);
+        self.names
+            .get(&id)
+            .cloned()
+            .or_else(|| blocks::user_name(user))
+            .unwrap_or(id)
+    }
+
+    pub fn link(&self, user: &User) -> String {
+        format!("[[{}]]", sanitize_filename(&self.name(user)))
+    }
+
+    // People / 作成者 / 最終更新者プロパティをプロパティ名ごとのリストとして出力する
+    pub fn people_frontmatter(&self, page: &Page) -> String {
+        let mut frontmatter = String::new();
+        for (name, people) in page_people(page) {
+            frontmatter.push_str(&format!("{}:\n", yaml_string(name)));
+            for user in people {
+                frontmatter.push_str(&format!("  - {}\n", yaml_string(&self.link(user))));
+            }
+        }
+        frontmatter
+    }
+
+    pub fn write_stub(&self, vault_dir: &Path, user: &User) -> Result<()> {
+        let Some(folder) = &self.stub_folder else {
+            return Ok(());
+        };
+
+        let dir = vault_dir.join(folder);
+        let filepath = dir.join(format!("{}.md", sanitize_filename(&self.name(user))));
+        if filepath.exists() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&dir).map_err(|e| NotionToObsidianError::file_write(&dir, e))?;
+        vault::write_atomic(
+            &filepath,
+            format!("---\nnotion_user_id: {}\n---\n", blocks::user_id(user)),
+        )
+    }
+}
+
+pub fn page_people(page: &Page) -> Vec<(&str, Vec<&User>)> {
+    let mut people: Vec<(&str, Vec<&User>)> = page
+        .properties
+        .iter()
+        .filter_map(|(name, prop)| match prop {
+            PageProperty::People { people, .. } => Some((name.as_str(), people.iter().collect())),
+            PageProperty::CreatedBy { created_by, .. } => Some((name.as_str(), vec![created_by])),
+            PageProperty::LastEditedBy { last_edited_by, .. } => {
+                Some((name.as_str(), vec![last_edited_by]))
+            }
+            _ => None,
+        })
+        .collect();
+    people.sort_by_key(|(name, _)| *name);
+    people
+}
+
+pub fn mentioned_users(tree: &[BlockNode]) -> Vec<(&str, &User)> {
+    blocks::mentions(tree)
+        .into_iter()
+        .filter_map(|(plain_text, mention)| match mention {
+            Mention::User { user } => Some((plain_text, user)),
+            _ => None,
+        })
+        .collect()
+}
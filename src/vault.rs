@@ -0,0 +1,74 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::error::{NotionToObsidianError, Result};
+
+// フロントマターのnotion_id（ハイフンなし）
+// Windowsで編集されたノートの改行（CRLF）も読み込む
+pub fn notion_id(content: &str) -> Option<String> {
+    let frontmatter = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+    let end = frontmatter.find("\n---")?;
+    frontmatter[..end].lines().find_map(|line| {
+        let id = line.strip_prefix("notion_id:")?.trim().trim_matches('"');
+        (!id.is_empty()).then(|| id.replace('-', ""))
+    })
+}
+
+// バルト内のノートをnotion_idで引けるようにする
+// 名前を変更・移動されたノートも元のページと対応付けるため、サブフォルダも検索する
+pub fn index_notes(vault_dir: &Path) -> HashMap<String, PathBuf> {
+    let mut index = HashMap::new();
+    let mut dirs = vec![vault_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "md") {
+                if let Some(id) = fs::read_to_string(&path).ok().and_then(|c| notion_id(&c)) {
+                    index.insert(id, path);
+                }
+            }
+        }
+    }
+    index
+}
+
+// 書き込み途中のファイル（同じフォルダに作成してrenameで置き換える）
+pub fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.tmp", name))
+}
+
+// 一時ファイルに書き込んでから置き換えるため、途中で中断しても元のファイルか新しいファイルのどちらかが残る
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+    copy_atomic(path, &mut content.as_ref())
+}
+
+// readerの内容をwrite_atomicと同じく書き込む（大きなファイルをメモリに読み込まない）
+pub fn copy_atomic(path: &Path, reader: &mut impl Read) -> Result<()> {
+    let temp = temp_path(path);
+    let result = File::create(&temp)
+        .and_then(|mut file| {
+            io::copy(reader, &mut file)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp, path));
+
+    result.map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        NotionToObsidianError::file_write(path, e)
+    })
+}
@@ -0,0 +1,130 @@
+// 複数のテストで使うNotionのブロック・ページのJSON
+#![allow(dead_code)]
+
+use notion_client::objects::{block::Block, page::Page};
+use notion_to_obsidian_rs::blocks::BlockNode;
+use serde_json::{json, Map, Value};
+
+pub const BLOCK_ID: &str = "1aeb266e-0c70-8060-a6fe-c6eb458e1379";
+
+pub fn rich_text(content: &str) -> Value {
+    json!({
+        "type": "text",
+        "text": { "content": content, "link": null },
+        "annotations": {
+            "bold": false,
+            "italic": false,
+            "strikethrough": false,
+            "underline": false,
+            "code": false,
+            "color": "default"
+        },
+        "plain_text": content,
+        "href": null
+    })
+}
+
+// mentionは {"type": "user", "user": {...}} などのメンションの値
+pub fn mention(mention: Value, plain_text: &str) -> Value {
+    json!({
+        "type": "mention",
+        "mention": mention,
+        "annotations": {
+            "bold": false,
+            "italic": false,
+            "strikethrough": false,
+            "underline": false,
+            "code": false,
+            "color": "default"
+        },
+        "plain_text": plain_text,
+        "href": null
+    })
+}
+
+pub fn user_mention(id: &str, name: &str) -> Value {
+    mention(
+        json!({
+            "type": "user",
+            "user": {
+                "object": "user",
+                "id": id,
+                "name": name,
+                "avatar_url": null,
+                "type": "person",
+                "person": { "email": "user@example.com" }
+            }
+        }),
+        &format!("@{}", name),
+    )
+}
+
+// notion_client 1.0の日付メンションは日時のみ読めるため、タイムゾーン付きの日時で作る
+pub fn date_mention(start: &str, plain_text: &str) -> Value {
+    mention(
+        json!({
+            "type": "date",
+            "date": { "start": start, "end": null, "time_zone": "Asia/Tokyo" }
+        }),
+        plain_text,
+    )
+}
+
+pub fn block(kind: &str, value: Value, children: Vec<BlockNode>) -> BlockNode {
+    block_with_id(BLOCK_ID, kind, value, children)
+}
+
+pub fn block_with_id(id: &str, kind: &str, value: Value, children: Vec<BlockNode>) -> BlockNode {
+    let mut object = Map::new();
+    object.insert("object".to_string(), json!("block"));
+    object.insert("id".to_string(), json!(id));
+    object.insert("has_children".to_string(), json!(!children.is_empty()));
+    object.insert("type".to_string(), json!(kind));
+    object.insert(kind.to_string(), value);
+
+    let block: Block = serde_json::from_value(Value::Object(object)).expect("invalid block");
+    BlockNode { block, children }
+}
+
+pub fn text_block(kind: &str, content: &str, children: Vec<BlockNode>) -> BlockNode {
+    block(
+        kind,
+        json!({ "rich_text": [rich_text(content)], "color": "default" }),
+        children,
+    )
+}
+
+pub fn paragraph(id: &str, content: &str) -> BlockNode {
+    block_with_id(
+        id,
+        "paragraph",
+        json!({ "rich_text": [rich_text(content)], "color": "default" }),
+        vec![],
+    )
+}
+
+// propertiesはプロパティ名からプロパティの値
+pub fn page(id: &str, properties: Value) -> Page {
+    let user = json!({ "object": "user", "id": "00000000-0000-0000-0000-000000000000" });
+    serde_json::from_value(json!({
+        "object": "page",
+        "id": id,
+        "created_time": "2025-03-06T08:40:00.000Z",
+        "created_by": user,
+        "last_edited_time": "2025-03-06T08:40:00.000Z",
+        "last_edited_by": user,
+        "cover": null,
+        "icon": null,
+        "parent": { "type": "workspace", "workspace": true },
+        "archived": false,
+        "in_trash": false,
+        "properties": properties,
+        "url": format!("https://www.notion.so/{}", id.replace('-', "")),
+        "public_url": null,
+    }))
+    .unwrap()
+}
+
+pub fn title_property(title: &str) -> Value {
+    json!({ "id": "title", "type": "title", "title": [rich_text(title)] })
+}
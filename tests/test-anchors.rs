@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+
+mod common;
+
+use common::{block_with_id, paragraph, rich_text};
+use notion_to_obsidian_rs::{
+    anchors::{heading_text, parse_block_link, rewrite_links, BlockLink},
+    renderer::{MarkdownRenderer, RenderOptions},
+};
+use serde_json::json;
+
+const PAGE_ID: &str = "1aeb266e0c708060a6fec6eb458e1379";
+const BLOCK_ID: &str = "1b4b266e0c7080d6bba2e7a6a1e0c2f4";
+
+#[test]
+fn test_parse_block_link() {
+    let expected = Some(BlockLink {
+        page_id: PAGE_ID.to_string(),
+        block_id: BLOCK_ID.to_string(),
+    });
+
+    assert_eq!(
+        parse_block_link(&format!(
+            "https://www.notion.so/workspace/Title-{}?pvs=4#{}",
+            PAGE_ID, BLOCK_ID
+        )),
+        expected
+    );
+    assert_eq!(
+        parse_block_link(&format!("/{}#{}", PAGE_ID, BLOCK_ID)),
+        expected
+    );
+    assert_eq!(
+        parse_block_link(&format!("https://www.notion.so/{}", PAGE_ID)),
+        None
+    );
+}
+
+#[test]
+fn test_block_links_are_rewritten_to_wiki_links() {
+    let titles = HashMap::from([(PAGE_ID.to_string(), "設計メモ".to_string())]);
+    let content = format!(
+        "[こちら](https://www.notion.so/{}#{})を参照\n[外部](https://example.com)",
+        PAGE_ID, BLOCK_ID
+    );
+
+    assert_eq!(
+        rewrite_links(&content, &titles, &HashMap::new()),
+        "[[設計メモ#^1b4b266e0c7080d6bba2e7a6a1e0c2f4|こちら]]を参照\n[外部](https://example.com)"
+    );
+}
+
+#[test]
+fn test_heading_links_use_heading_text_and_escape_pipes_in_tables() {
+    let titles = HashMap::from([(PAGE_ID.to_string(), "設計メモ".to_string())]);
+    let headings = HashMap::from([(BLOCK_ID.to_string(), "概要 設計".to_string())]);
+    let content = format!(
+        "[見出し](/{page}#{block})\n| [セル](/{page}#{other}) | 値 |",
+        page = PAGE_ID,
+        block = BLOCK_ID,
+        other = "1ceb266e0c708060a6fec6eb458e1379"
+    );
+
+    assert_eq!(
+        rewrite_links(&content, &titles, &headings),
+        "[[設計メモ#概要 設計|見出し]]\n| [[設計メモ#^1ceb266e0c708060a6fec6eb458e1379\\|セル]] | 値 |"
+    );
+}
+
+#[test]
+fn test_heading_text_removes_link_characters() {
+    let heading = block_with_id(
+        BLOCK_ID,
+        "heading_2",
+        json!({ "rich_text": [rich_text("手順: A|B #1")], "color": "default" }),
+        vec![],
+    );
+
+    assert_eq!(heading_text(&heading.block).as_deref(), Some("手順 A B 1"));
+    assert_eq!(heading_text(&paragraph(BLOCK_ID, "本文").block), None);
+}
+
+#[test]
+fn test_referenced_blocks_get_anchors() {
+    // 同じ本文のブロックがあっても参照されているブロックにのみ付け、引用は次の行に付ける
+    // 見出しはテキストでリンクするため付けない
+    let tree = vec![
+        block_with_id(
+            "1deb266e-0c70-8060-a6fe-c6eb458e1379",
+            "heading_2",
+            json!({ "rich_text": [rich_text("見出し")], "color": "default" }),
+            vec![],
+        ),
+        paragraph("1aeb266e-0c70-8060-a6fe-c6eb458e1379", "同じ本文"),
+        paragraph("1b4b266e-0c70-80d6-bba2-e7a6a1e0c2f4", "同じ本文"),
+        block_with_id(
+            "1ceb266e-0c70-8060-a6fe-c6eb458e1379",
+            "quote",
+            json!({ "rich_text": [rich_text("引用")], "color": "default" }),
+            vec![],
+        ),
+    ];
+    let options = RenderOptions {
+        anchors: HashSet::from([
+            BLOCK_ID.to_string(),
+            "1ceb266e0c708060a6fec6eb458e1379".to_string(),
+            "1deb266e0c708060a6fec6eb458e1379".to_string(),
+        ]),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        MarkdownRenderer::new().render_with(&tree, &options),
+        "## 見出し\n\n同じ本文\n\n同じ本文 ^1b4b266e0c7080d6bba2e7a6a1e0c2f4\n\n> 引用\n\n^1ceb266e0c708060a6fec6eb458e1379"
+    );
+}
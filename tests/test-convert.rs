@@ -1,14 +1,13 @@
-use env_logger;
 use log::info;
 use notion_to_obsidian_rs::{
     builder::NotionToObsidianBuilder,
+    migration::CancellationToken,
     traits::{post_processor::MyPostProcessor, MyFrontmatterGenerator, SinglePageProvider},
     Result,
 };
 use std::fs;
 use std::path::PathBuf;
 use std::time::Instant;
-use tokio;
 
 const TEST_PAGE_ID: &str = "1aeb266e0c708060a6fec6eb458e1379";
 const TEST_OUTPUT_PAGE_TITLE: &str = "test";
@@ -42,7 +41,9 @@ async fn test_page_conversion() -> Result<()> {
         .with_output_path(TEST_OUTPUT_DIR.to_string())
         .with_page_provider(Box::new(SinglePageProvider::new(TEST_PAGE_ID.to_string())))
         .with_frontmatter_generator(Box::new(
-            MyFrontmatterGenerator::new(&tag_database_id, notion_token.clone()).await,
+            MyFrontmatterGenerator::new(&tag_database_id, notion_token.clone())
+                .await
+                .expect("Failed to load tags"),
         ))
         .with_post_processor(Box::new(MyPostProcessor {}))
         .build()
@@ -53,7 +54,7 @@ async fn test_page_conversion() -> Result<()> {
 
     // テスト対象のページを変換
     let start_conversion = Instant::now();
-    converter.migrate_pages().await?;
+    converter.migrate_pages(&CancellationToken::new()).await?;
     info!("ページ変換完了: {:?}", start_conversion.elapsed());
 
     // テストケースのファイルを読み込み
@@ -0,0 +1,79 @@
+use std::collections::{BTreeMap, HashMap};
+
+use notion_to_obsidian_rs::{
+    doctor::{
+        check_output_dir, check_properties, property_types, CheckStatus, PropertyRequirement,
+    },
+    properties,
+};
+use serde_json::{json, Value};
+
+fn requirements() -> Vec<PropertyRequirement> {
+    vec![
+        PropertyRequirement::new(properties::MIGRATED, &["checkbox"]),
+        PropertyRequirement::new(properties::CREATED_TIME, &["created_time", "date"]),
+        PropertyRequirement::new(properties::TAG_NAME, &["title"]),
+    ]
+}
+
+#[test]
+fn test_check_properties_reports_missing_and_wrong_types() {
+    let types: BTreeMap<String, String> = [
+        ("移行済み", "select"),
+        ("作成日時", "date"),
+        ("タイトル", "title"),
+    ]
+    .into_iter()
+    .map(|(name, kind)| (name.to_string(), kind.to_string()))
+    .collect();
+
+    let checks = check_properties("ALL_DATABASE_ID", &types, &requirements());
+    let statuses: Vec<CheckStatus> = checks.iter().map(|c| c.status).collect();
+    assert_eq!(
+        statuses,
+        vec![CheckStatus::Error, CheckStatus::Ok, CheckStatus::Error]
+    );
+    // 問題のあるプロパティには対処方法を付ける
+    assert!(checks[0].fix.is_some());
+    assert!(checks[1].fix.is_none());
+    assert!(checks[2].fix.is_some());
+}
+
+#[test]
+fn test_property_types_reads_database_schema() {
+    // データベースのスキーマのプロパティ（ページがなくても取得できる）
+    let schema: HashMap<String, Value> = [
+        (
+            "移行済み",
+            json!({ "id": "a", "name": "移行済み", "type": "checkbox", "checkbox": {} }),
+        ),
+        (
+            "作成日時",
+            json!({ "id": "b", "name": "作成日時", "type": "created_time", "created_time": {} }),
+        ),
+        (
+            "名前",
+            json!({ "id": "title", "name": "名前", "type": "title", "title": {} }),
+        ),
+    ]
+    .into_iter()
+    .map(|(name, property)| (name.to_string(), property))
+    .collect();
+
+    let checks = check_properties("ALL_DATABASE_ID", &property_types(&schema), &requirements());
+    assert!(checks.iter().all(|c| c.status == CheckStatus::Ok));
+}
+
+#[test]
+fn test_check_output_dir() {
+    let dir = std::env::temp_dir().join("notion-to-obsidian-doctor-test");
+    let _ = std::fs::remove_dir_all(&dir);
+    assert_eq!(check_output_dir(&dir).status, CheckStatus::Error);
+
+    std::fs::create_dir_all(&dir).expect("failed to create dir");
+    assert_eq!(check_output_dir(&dir).status, CheckStatus::Ok);
+
+    let file = dir.join("note.md");
+    std::fs::write(&file, "").expect("failed to write");
+    assert_eq!(check_output_dir(&file).status, CheckStatus::Error);
+}
@@ -0,0 +1,77 @@
+use std::error::Error;
+
+use notion_client::NotionClientError;
+use notion_to_obsidian_rs::{
+    error::{ErrorKind, NotionApiError},
+    NotionToObsidianError,
+};
+use serde_json::json;
+
+// Notion APIのエラーレスポンスを受け取ったときのnotion_clientのエラー
+fn response(status: u16, code: &str) -> NotionClientError {
+    NotionClientError::InvalidStatusCode {
+        error: serde_json::from_value(json!({
+            "object": "error",
+            "status": status,
+            "code": code,
+            "message": "",
+        }))
+        .unwrap(),
+    }
+}
+
+#[test]
+fn test_api_errors_keep_status_and_kind() {
+    let rate_limited = NotionToObsidianError::page_retrieval("abc", response(429, "rate_limited"));
+    assert_eq!(rate_limited.status(), Some(429));
+    assert_eq!(rate_limited.kind(), ErrorKind::RateLimited);
+    assert!(rate_limited.is_retryable());
+
+    let not_found =
+        NotionToObsidianError::block_retrieval("abc", response(404, "object_not_found"));
+    assert_eq!(not_found.kind(), ErrorKind::NotFound);
+    assert!(!not_found.is_retryable());
+
+    let forbidden = NotionToObsidianError::page_update("abc", response(403, "restricted_resource"));
+    assert_eq!(forbidden.kind(), ErrorKind::PermissionDenied);
+    // ステータスはメッセージに一度だけ含める
+    assert_eq!(forbidden.to_string().matches("HTTP 403").count(), 1);
+
+    // notion_clientを使わない呼び出しのエラーレスポンスも同じように分類する
+    let server_error = NotionToObsidianError::PageRetrievalError {
+        page_id: "abc".to_string(),
+        source: NotionApiError::with_status(503, "Service Unavailable"),
+    };
+    assert_eq!(server_error.status(), Some(503));
+    assert_eq!(server_error.kind(), ErrorKind::ServerError);
+    assert!(server_error.is_retryable());
+}
+
+#[test]
+fn test_post_process_error_keeps_source_chain() {
+    let error = NotionToObsidianError::post_process(
+        "set_checkbox",
+        NotionToObsidianError::page_update("abc", response(502, "service_unavailable")),
+    );
+    assert_eq!(error.code(), "POST_PROCESS");
+    assert_eq!(error.status(), Some(502));
+    assert!(error.is_retryable());
+
+    let source = error.source().expect("no source");
+    let inner = source
+        .downcast_ref::<NotionToObsidianError>()
+        .expect("source is not NotionToObsidianError");
+    assert_eq!(inner.code(), "PAGE_UPDATE");
+    assert!(inner.source().and_then(|e| e.source()).is_some());
+}
+
+#[test]
+fn test_file_errors_are_io() {
+    let error = NotionToObsidianError::file_read(
+        "/vault/.notion-to-obsidian/journal.json",
+        serde_json::from_str::<u8>("{").unwrap_err(),
+    );
+    assert_eq!(error.kind(), ErrorKind::Io);
+    assert!(!error.is_retryable());
+    assert!(error.source().is_some());
+}
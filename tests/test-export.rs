@@ -0,0 +1,220 @@
+use std::io::Write;
+
+use notion_to_obsidian_rs::export::{
+    insert_properties, parse_csv, split_id, strip_ids, NotionExport,
+};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+fn entry(path: &str, content: &str) -> (String, Vec<u8>) {
+    (path.to_string(), content.as_bytes().to_vec())
+}
+
+fn export() -> NotionExport {
+    NotionExport::from_entries(vec![
+        entry(
+            "読書メモ 1aeb266e0c708060a6fec6eb458e1379.md",
+            "# 読書メモ\n\n[本棚](%E8%AA%AD%E6%9B%B8%E3%83%A1%E3%83%A2%201aeb266e0c708060a6fec6eb458e1379/%E6%9C%AC%E6%A3%9A%202beb266e0c708060a6fec6eb458e1379.csv)\n",
+        ),
+        entry(
+            "読書メモ 1aeb266e0c708060a6fec6eb458e1379/本棚 2beb266e0c708060a6fec6eb458e1379.csv",
+            "\u{feff}名前,著者,関連\n吾輩は猫である,夏目漱石,\n",
+        ),
+        entry(
+            "読書メモ 1aeb266e0c708060a6fec6eb458e1379/本棚 2beb266e0c708060a6fec6eb458e1379_all.csv",
+            "\u{feff}名前,著者,関連\n吾輩は猫である,夏目漱石,坊っちゃん (%E5%9D%8A%E3%81%A3%E3%81%A1%E3%82%83%E3%82%93%204ceb266e0c708060a6fec6eb458e1379.md)\n坊っちゃん,夏目漱石,\n",
+        ),
+        entry(
+            "読書メモ 1aeb266e0c708060a6fec6eb458e1379/本棚 2beb266e0c708060a6fec6eb458e1379/吾輩は猫である 3ceb266e0c708060a6fec6eb458e1379.md",
+            "# 吾輩は猫である\n\n著者: 夏目漱石\n関連: 坊っちゃん (%E5%9D%8A%E3%81%A3%E3%81%A1%E3%82%83%E3%82%93%204ceb266e0c708060a6fec6eb458e1379.md)\n\n[次に読む](%E5%9D%8A%E3%81%A3%E3%81%A1%E3%82%83%E3%82%93%204ceb266e0c708060a6fec6eb458e1379.md)と[トップ](../../%E8%AA%AD%E6%9B%B8%E3%83%A1%E3%83%A2%201aeb266e0c708060a6fec6eb458e1379.md)\n\n![cover.png](%E5%90%BE%E8%BC%A9%E3%81%AF%E7%8C%AB%E3%81%A7%E3%81%82%E3%82%8B%203ceb266e0c708060a6fec6eb458e1379/cover.png)\n\n[Notion](https://www.notion.so/)\n",
+        ),
+        entry(
+            "読書メモ 1aeb266e0c708060a6fec6eb458e1379/本棚 2beb266e0c708060a6fec6eb458e1379/吾輩は猫である 3ceb266e0c708060a6fec6eb458e1379/cover.png",
+            "png",
+        ),
+        entry(
+            "読書メモ 1aeb266e0c708060a6fec6eb458e1379/本棚 2beb266e0c708060a6fec6eb458e1379/坊っちゃん 4ceb266e0c708060a6fec6eb458e1379.md",
+            "# 坊っちゃん\n\n著者: 夏目漱石\n\n本文\n",
+        ),
+    ])
+}
+
+#[test]
+fn test_split_and_strip_ids() {
+    assert_eq!(
+        split_id("メモ 1aeb266e0c708060a6fec6eb458e1379"),
+        ("メモ", Some("1aeb266e0c708060a6fec6eb458e1379"))
+    );
+    assert_eq!(split_id("メモ 2024"), ("メモ 2024", None));
+    assert_eq!(
+        strip_ids("v1.2 メモ 1aeb266e0c708060a6fec6eb458e1379/本棚 2beb266e0c708060a6fec6eb458e1379_all.csv"),
+        "v1.2 メモ/本棚.csv"
+    );
+    assert_eq!(strip_ids("メモ/image.png"), "メモ/image.png");
+}
+
+#[test]
+fn test_parse_csv() {
+    let rows = parse_csv("\u{feff}名前,メモ\r\n\"A, B\",\"1行目\n\"\"2行目\"\"\"\r\n,\r\n");
+    assert_eq!(
+        rows,
+        vec![
+            vec!["名前".to_string(), "メモ".to_string()],
+            vec!["A, B".to_string(), "1行目\n\"2行目\"".to_string()],
+        ]
+    );
+}
+
+#[test]
+fn test_database_rows_get_csv_properties() {
+    let export = export();
+    let page = export.page("3ceb266e-0c70-8060-a6fe-c6eb458e1379").unwrap();
+
+    assert_eq!(page.title, "吾輩は猫である");
+    // _all.csvを優先する
+    assert_eq!(
+        page.properties,
+        vec![
+            ("著者".to_string(), "夏目漱石".to_string()),
+            (
+                "関連".to_string(),
+                "坊っちゃん (%E5%9D%8A%E3%81%A3%E3%81%A1%E3%82%83%E3%82%93%204ceb266e0c708060a6fec6eb458e1379.md)".to_string()
+            ),
+        ]
+    );
+    // タイトルの見出しとプロパティの行は本文に含めない
+    assert!(page.body.starts_with("\n[次に読む]"));
+
+    let frontmatter = insert_properties("---\ntitle: \"吾輩は猫である\"\n---\n", &page.properties);
+    assert_eq!(
+        frontmatter,
+        "---\ntitle: \"吾輩は猫である\"\n著者: \"夏目漱石\"\n関連:\n  - \"[[坊っちゃん]]\"\n---\n"
+    );
+}
+
+#[test]
+fn test_render_rewrites_links() {
+    let export = export();
+    let page = export.page("3ceb266e-0c70-8060-a6fe-c6eb458e1379").unwrap();
+
+    let (body, attachments) = export.render(page, Some("attachments"));
+    assert_eq!(
+        body,
+        "\n[[坊っちゃん|次に読む]]と[[読書メモ|トップ]]\n\n![[3ceb266e0c708060a6fec6eb458e1379-cover.png]]\n\n[Notion](https://www.notion.so/)"
+    );
+    assert_eq!(
+        attachments,
+        vec![(
+            "読書メモ 1aeb266e0c708060a6fec6eb458e1379/本棚 2beb266e0c708060a6fec6eb458e1379/吾輩は猫である 3ceb266e0c708060a6fec6eb458e1379/cover.png".to_string(),
+            "attachments/3ceb266e0c708060a6fec6eb458e1379-cover.png".to_string()
+        )]
+    );
+
+    // フォルダを指定しない場合はIDを除いたパスにコピーする
+    let (body, _) = export.render(page, None);
+    assert!(body.contains("![[読書メモ/本棚/吾輩は猫である/cover.png]]"));
+
+    // データベースへのリンクは文字列のみ残す
+    let top = export.page("1aeb266e-0c70-8060-a6fe-c6eb458e1379").unwrap();
+    assert_eq!(export.render(top, None).0.trim(), "本棚");
+}
+
+#[test]
+fn test_render_handles_parentheses_and_code_blocks() {
+    let export = NotionExport::from_entries(vec![
+        entry(
+            "メモ 1aeb266e0c708060a6fec6eb458e1379.md",
+            "# メモ\n\n![](%E3%83%A1%E3%83%A2%201aeb266e0c708060a6fec6eb458e1379/image%20(1).png)\n\n```\n[コード](image%20(1).png)\n```\n",
+        ),
+        entry("メモ 1aeb266e0c708060a6fec6eb458e1379/image (1).png", "png"),
+    ]);
+    let page = export.page("1aeb266e-0c70-8060-a6fe-c6eb458e1379").unwrap();
+
+    let (body, attachments) = export.render(page, None);
+    assert_eq!(
+        body,
+        "\n![[メモ/image (1).png]]\n\n```\n[コード](image%20(1).png)\n```\n"
+    );
+    assert_eq!(attachments.len(), 1);
+}
+
+#[test]
+fn test_unknown_times_and_urls_are_omitted() {
+    let frontmatter = "---\ntitle: \"吾輩は猫である\"\nnotion_id: 3ceb266e-0c70-8060-a6fe-c6eb458e1379\nnotion_url: https://www.notion.so/3ceb266e0c708060a6fec6eb458e1379\ncreated: 1970-01-01 00:00\nupdated: 1970-01-01 00:00\n---\n";
+
+    // CSVに作成日時の列がない
+    let export = export();
+    let page = export.page("3ceb266e-0c70-8060-a6fe-c6eb458e1379").unwrap();
+    assert_eq!(page.created_time, None);
+    assert_eq!(
+        page.remove_unknown_keys(frontmatter),
+        "---\ntitle: \"吾輩は猫である\"\nnotion_id: 3ceb266e-0c70-8060-a6fe-c6eb458e1379\nnotion_url: https://www.notion.so/3ceb266e0c708060a6fec6eb458e1379\n---\n"
+    );
+
+    // IDのないページはIDとURLも除く
+    let export = NotionExport::from_entries(vec![
+        entry(
+            "本棚.csv",
+            "名前,Created,Last edited\nメモ,\"March 5, 2024 3:04 PM\",\"March 6, 2024 10:30 AM\"\n",
+        ),
+        entry(
+            "本棚/メモ.md",
+            "# メモ\n\nCreated: March 5, 2024 3:04 PM\n\n本文\n",
+        ),
+    ]);
+    let page = &export.pages()[0];
+    let created = page.created_time.unwrap().with_timezone(&chrono::Local);
+    assert_eq!(
+        created.format("%Y-%m-%d %H:%M").to_string(),
+        "2024-03-05 15:04"
+    );
+    assert!(page.last_edited_time.is_some());
+    assert_eq!(
+        page.remove_unknown_keys(frontmatter),
+        "---\ntitle: \"吾輩は猫である\"\ncreated: 1970-01-01 00:00\nupdated: 1970-01-01 00:00\n---\n"
+    );
+
+    let error = export.require_page("3ceb266e-0c70-8060-a6fe-c6eb458e1379");
+    assert_eq!(error.unwrap_err().code(), "PAGE_NOT_IN_EXPORT");
+}
+
+#[test]
+fn test_open_reads_nested_zip() {
+    let zip = |entries: &[(&str, &[u8])]| {
+        let mut writer = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    };
+    let part = zip(&[
+        (
+            "メモ 1aeb266e0c708060a6fec6eb458e1379.md",
+            "# メモ\n\n本文\n".as_bytes(),
+        ),
+        ("メモ 1aeb266e0c708060a6fec6eb458e1379/image.png", b"png"),
+    ]);
+    let outer = zip(&[("Export-Part-1.zip", part.as_slice())]);
+
+    let dir = std::env::temp_dir().join("notion-to-obsidian-test-export");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("export.zip");
+    std::fs::write(&path, outer).unwrap();
+
+    let export = NotionExport::open(&path).unwrap();
+    assert_eq!(export.pages().len(), 1);
+    assert_eq!(export.pages()[0].id, "1aeb266e-0c70-8060-a6fe-c6eb458e1379");
+    assert_eq!(export.pages()[0].title, "メモ");
+
+    // 添付ファイルは分割したzipから読み込む
+    let destination = dir.join("image.png");
+    export
+        .copy_file(
+            "メモ 1aeb266e0c708060a6fec6eb458e1379/image.png",
+            &destination,
+        )
+        .unwrap();
+    assert_eq!(std::fs::read(&destination).unwrap(), b"png");
+}
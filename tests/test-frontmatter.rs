@@ -0,0 +1,53 @@
+mod common;
+
+use notion_to_obsidian_rs::traits::frontmatter_generator::collect_tags;
+use serde_json::json;
+
+use common::{page, title_property};
+
+#[tokio::test]
+async fn test_tags_are_loaded_across_pages() {
+    let mut cursors = Vec::new();
+    let tags = collect_tags(|cursor| {
+        cursors.push(cursor.clone());
+        let response = match cursor.as_deref() {
+            None => (
+                vec![page(
+                    "1aeb266e-0c70-8060-a6fe-c6eb458e1379",
+                    json!({ "名前": title_property("Rust") }),
+                )],
+                Some("cursor-2".to_string()),
+            ),
+            _ => (
+                vec![
+                    page(
+                        "2beb266e-0c70-8060-a6fe-c6eb458e1379",
+                        json!({ "名前": title_property("Memo") }),
+                    ),
+                    // タイトルが空のタグは読み込まない
+                    page(
+                        "3ceb266e-0c70-8060-a6fe-c6eb458e1379",
+                        json!({ "名前": { "id": "title", "type": "title", "title": [] } }),
+                    ),
+                ],
+                None,
+            ),
+        };
+        async move { Ok(response) }
+    })
+    .await
+    .expect("failed to load tags");
+
+    assert_eq!(cursors, vec![None, Some("cursor-2".to_string())]);
+    assert_eq!(tags.len(), 2);
+    assert_eq!(
+        tags.get("1aeb266e-0c70-8060-a6fe-c6eb458e1379")
+            .map(String::as_str),
+        Some("Rust")
+    );
+    assert_eq!(
+        tags.get("2beb266e-0c70-8060-a6fe-c6eb458e1379")
+            .map(String::as_str),
+        Some("Memo")
+    );
+}
@@ -0,0 +1,44 @@
+use notion_to_obsidian_rs::{
+    i18n::{self, Language, Msg},
+    tr, NotionToObsidianError,
+};
+
+#[test]
+fn test_parse_locale() {
+    assert_eq!(Language::parse("ja_JP.UTF-8"), Some(Language::Ja));
+    assert_eq!(Language::parse("en-US"), Some(Language::En));
+    assert_eq!(Language::parse("C"), None);
+}
+
+// 言語の設定はプロセス全体で共有されるため、1つのテストで切り替えて確認する
+#[test]
+fn test_messages_follow_language_and_codes_do_not() {
+    let error = NotionToObsidianError::page_retrieval("abc", "timeout");
+
+    i18n::set_language(Language::En);
+    assert_eq!(
+        error.to_string(),
+        "Failed to retrieve Notion page abc: timeout"
+    );
+    assert_eq!(tr!(Msg::Finished, 3, 5), "Done: converted 3 / 5 pages");
+
+    i18n::set_language(Language::Ja);
+    assert_eq!(
+        error.to_string(),
+        "Notionのページ abc の取得に失敗しました: timeout"
+    );
+    assert_eq!(
+        tr!(Msg::Finished, 3, 5),
+        "変換完了: 3 / 5 ページを変換しました"
+    );
+
+    assert_eq!(error.code(), "PAGE_RETRIEVAL");
+    assert_eq!(NotionToObsidianError::NoCheckpoint.code(), "NO_CHECKPOINT");
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "wrong number of arguments for Finished")]
+fn test_wrong_number_of_arguments_is_detected() {
+    tr!(Msg::Finished, 3);
+}
@@ -0,0 +1,33 @@
+use notion_to_obsidian_rs::markdown::normalize;
+
+#[test]
+fn test_nested_list_is_indented_to_parent_marker_width() {
+    let input = "1. 番号1\n2. 番号2\n  1. 子a\n  2. 子b\n    - 孫\n3. 番号3";
+    let expected = "1. 番号1\n2. 番号2\n   1. 子a\n   2. 子b\n      - 孫\n3. 番号3\n";
+
+    assert_eq!(normalize(input), expected);
+}
+
+#[test]
+fn test_redundant_blank_lines_are_collapsed() {
+    let input = "> 引用\n\n\n\n![](https://example.com/video.mp4)\n\n\n\n本文";
+    let expected = "> 引用\n\n![](https://example.com/video.mp4)\n\n本文\n";
+
+    assert_eq!(normalize(input), expected);
+}
+
+#[test]
+fn test_headings_tables_and_fences_are_surrounded_by_blank_lines() {
+    let input = "本文\n# 見出し１\n## 見出し2\n| a | i |\n| --- | --- |\n| u | e |\n```rust\naaa\n```\n本文";
+    let expected = "本文\n\n# 見出し１\n\n## 見出し2\n\n| a | i |\n| --- | --- |\n| u | e |\n\n```rust\naaa\n```\n\n本文\n";
+
+    assert_eq!(normalize(input), expected);
+}
+
+#[test]
+fn test_code_fence_in_list_keeps_its_content() {
+    let input = "- item\n  ```rust\n  fn main() {\n      println!();\n  }\n  ```\n  1. a";
+    let expected = "- item\n\n  ```rust\n  fn main() {\n      println!();\n  }\n  ```\n\n  1. a\n";
+
+    assert_eq!(normalize(input), expected);
+}
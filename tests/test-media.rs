@@ -0,0 +1,90 @@
+mod common;
+
+use notion_to_obsidian_rs::media::{self, EmbedFallback, MediaIndex, PageAssets};
+use serde_json::json;
+
+use common::page;
+
+#[test]
+fn test_video_ids_are_extracted_from_common_urls() {
+    for url in [
+        "https://www.youtube.com/watch?v=M-Eyhjkepy0",
+        "https://www.youtube.com/watch?feature=share&v=M-Eyhjkepy0",
+        "https://youtu.be/M-Eyhjkepy0",
+        "https://www.youtube.com/shorts/M-Eyhjkepy0",
+    ] {
+        assert_eq!(
+            media::youtube_id(url).as_deref(),
+            Some("M-Eyhjkepy0"),
+            "{}",
+            url
+        );
+    }
+
+    assert_eq!(
+        media::vimeo_id("https://vimeo.com/76979871").as_deref(),
+        Some("76979871")
+    );
+    assert_eq!(media::youtube_id("https://example.com/watch?v=1"), None);
+}
+
+#[test]
+fn test_embed_fallback_for_other_domains() {
+    let link = MediaIndex::default();
+    assert_eq!(
+        media::render_embed("https://example.com/map", "地図", &link),
+        "[地図](https://example.com/map)"
+    );
+
+    let iframe = MediaIndex {
+        embed_fallback: EmbedFallback::Iframe,
+        ..Default::default()
+    };
+    assert!(media::render_embed("https://example.com/map", "", &iframe)
+        .starts_with("<iframe src=\"https://example.com/map\""));
+}
+
+#[test]
+fn test_page_assets_frontmatter() {
+    let assets = PageAssets {
+        icon: Some("🚀".to_string()),
+        banner: Some("![[attachments/1aeb266e-cover.png]]".to_string()),
+    };
+
+    assert_eq!(
+        assets.frontmatter(),
+        "icon: \"🚀\"\nbanner: \"![[attachments/1aeb266e-cover.png]]\"\n"
+    );
+    assert_eq!(PageAssets::default().frontmatter(), "");
+}
+
+#[test]
+fn test_page_assets_use_attachments_folder() {
+    let mut page = page("1aeb266e-0c70-8060-a6fe-c6eb458e1379", json!({}));
+    page.icon = serde_json::from_value(json!({
+        "type": "file",
+        "file": { "url": "https://s3.amazonaws.com/icon.png?X-Amz-Expires=3600", "expiry_time": "2025-03-06T09:40:00.000Z" }
+    }))
+    .unwrap();
+    page.cover = serde_json::from_value(json!({
+        "type": "external",
+        "external": { "url": "https://example.com/cover.jpg" }
+    }))
+    .unwrap();
+
+    assert_eq!(
+        PageAssets::from_page(&page, Some("attachments")),
+        PageAssets {
+            icon: Some("attachments/1aeb266e0c708060a6fec6eb458e1379-icon.png".to_string()),
+            banner: Some("![[attachments/1aeb266e0c708060a6fec6eb458e1379-cover.jpg]]".to_string()),
+        }
+    );
+    // ダウンロード先がない場合は期限付きのURLを出力しない
+    assert_eq!(PageAssets::from_page(&page, None), PageAssets::default());
+
+    page.icon = serde_json::from_value(json!({ "type": "emoji", "emoji": "🚀" })).unwrap();
+    assert_eq!(
+        PageAssets::from_page(&page, None).icon.as_deref(),
+        Some("🚀")
+    );
+}
@@ -0,0 +1,216 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use notion_to_obsidian_rs::{
+    builder::NotionToObsidianBuilder,
+    export::NotionExport,
+    migration::{
+        CancellationToken, Checkpoint, Journal, JournalEntry, JournalStage, MigrationReport,
+        PageOutcome, PageResult,
+    },
+    traits::{MigrationEvent, MigrationObserver},
+    vault,
+};
+
+fn result(outcome: PageOutcome) -> PageResult {
+    PageResult {
+        page_id: "1aeb266e-0c70-8060-a6fe-c6eb458e1379".to_string(),
+        title: "メモ".to_string(),
+        outcome,
+    }
+}
+
+#[test]
+fn test_report_distinguishes_partial_success() {
+    let report = MigrationReport {
+        pages: vec![
+            result(PageOutcome::Migrated),
+            result(PageOutcome::PartiallyMigrated {
+                error: "後処理に失敗しました".to_string(),
+            }),
+            result(PageOutcome::Failed {
+                error: "変換処理に失敗しました".to_string(),
+            }),
+        ],
+        retried: vec![],
+        cancelled: false,
+    };
+
+    assert_eq!(report.total(), 3);
+    assert_eq!(report.migrated(), 1);
+    assert_eq!(report.partially_migrated(), 1);
+    assert_eq!(report.failed(), 1);
+}
+
+#[test]
+fn test_journal_is_persisted_until_removed() {
+    let dir = std::env::temp_dir().join("notion-to-obsidian-journal-test");
+    let _ = std::fs::remove_dir_all(&dir);
+    let entry = JournalEntry {
+        title: "メモ".to_string(),
+        note: PathBuf::from("/vault/メモ.md"),
+        stage: JournalStage::Marking,
+        error: "後処理に失敗しました".to_string(),
+        completed: vec!["set_checkbox:移行済み=true".to_string()],
+    };
+
+    let mut journal = Journal::load(&dir).expect("failed to load journal");
+    journal
+        .record("page-1", entry.clone())
+        .expect("failed to save journal");
+
+    let mut journal = Journal::load(&dir).expect("failed to load journal");
+    assert_eq!(journal.entries().get("page-1"), Some(&entry));
+
+    journal.remove("page-1").expect("failed to save journal");
+    let journal = Journal::load(&dir).expect("failed to load journal");
+    assert!(journal.entries().is_empty());
+}
+
+#[test]
+fn test_atomic_write_replaces_file_without_leaving_temp_file() {
+    let dir = std::env::temp_dir().join("notion-to-obsidian-atomic-test");
+    std::fs::create_dir_all(&dir).expect("failed to create dir");
+    let path = dir.join("メモ.md");
+
+    vault::write_atomic(&path, "古い内容").expect("failed to write");
+    vault::write_atomic(&path, "新しい内容").expect("failed to write");
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "新しい内容");
+    assert!(!vault::temp_path(&path).exists());
+}
+
+#[test]
+fn test_checkpoint_moves_completed_pages_out_of_pending() {
+    let dir = std::env::temp_dir().join("notion-to-obsidian-checkpoint-test");
+    let _ = std::fs::remove_dir_all(&dir);
+    assert_eq!(Checkpoint::load(&dir).expect("failed to load"), None);
+
+    let mut checkpoint = Checkpoint::new(vec![
+        "page-1".to_string(),
+        "page-2".to_string(),
+        "page-3".to_string(),
+    ]);
+    checkpoint.complete("page-1");
+    checkpoint.save(&dir).expect("failed to save checkpoint");
+    assert!(Checkpoint::exists(&dir));
+
+    let loaded = Checkpoint::load(&dir)
+        .expect("failed to load")
+        .expect("checkpoint not found");
+    assert_eq!(loaded.completed, vec!["page-1"]);
+    assert_eq!(loaded.pending, vec!["page-2", "page-3"]);
+
+    Checkpoint::remove(&dir).expect("failed to remove checkpoint");
+    assert!(!Checkpoint::exists(&dir));
+    assert_eq!(Checkpoint::load(&dir).expect("failed to load"), None);
+}
+
+#[test]
+fn test_cancellation_is_shared_between_clones() {
+    let token = CancellationToken::new();
+    let handle = token.clone();
+    assert!(!token.is_cancelled());
+
+    handle.cancel();
+    assert!(token.is_cancelled());
+}
+
+struct RecordingObserver(Arc<Mutex<Vec<MigrationEvent>>>);
+
+impl MigrationObserver for RecordingObserver {
+    fn on_event(&self, event: &MigrationEvent) {
+        self.0.lock().unwrap().push(event.clone());
+    }
+}
+
+#[tokio::test]
+async fn test_observer_receives_events_in_order() {
+    let dir = std::env::temp_dir().join("notion-to-obsidian-observer-test");
+    let _ = std::fs::remove_dir_all(&dir);
+    // 同じ名前のフォルダがあるためノートを書き込めない
+    std::fs::create_dir_all(dir.join("失敗.md")).expect("failed to create dir");
+    // 前回の実行で書き込みが中断したページと、後処理に失敗したページ
+    let mut journal = Journal::load(&dir).expect("failed to load journal");
+    journal
+        .record(
+            "page-0",
+            JournalEntry {
+                title: "中断".to_string(),
+                note: dir.join("中断.md"),
+                stage: JournalStage::Writing,
+                error: String::new(),
+                completed: Vec::new(),
+            },
+        )
+        .expect("failed to save journal");
+    journal
+        .record(
+            "page-1",
+            JournalEntry {
+                title: "後処理".to_string(),
+                note: dir.join("後処理.md"),
+                stage: JournalStage::Marking,
+                error: "後処理に失敗しました".to_string(),
+                completed: Vec::new(),
+            },
+        )
+        .expect("failed to save journal");
+
+    let export = NotionExport::from_entries(vec![
+        (
+            "失敗 2beb266e0c708060a6fec6eb458e1379.md".to_string(),
+            "# 失敗\n".as_bytes().to_vec(),
+        ),
+        (
+            "成功 1aeb266e0c708060a6fec6eb458e1379.md".to_string(),
+            "# 成功\n\n本文\n".as_bytes().to_vec(),
+        ),
+    ]);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let converter = NotionToObsidianBuilder::new(String::new())
+        .with_output_path(dir.to_string_lossy().to_string())
+        .with_export(Arc::new(export))
+        .with_observer(Box::new(RecordingObserver(events.clone())))
+        .build()
+        .expect("failed to build converter");
+    let report = converter
+        .migrate_pages(&CancellationToken::new())
+        .await
+        .expect("failed to migrate");
+    assert_eq!(report.migrated(), 1);
+    assert_eq!(report.failed(), 1);
+    assert_eq!(report.retried.len(), 1);
+    // エクスポートからの変換では後処理を再試行せず、ジャーナルに残す
+    let journal = Journal::load(&dir).expect("failed to load journal");
+    assert_eq!(journal.entries().keys().collect::<Vec<_>>(), vec!["page-1"]);
+
+    let events: Vec<(&str, String)> = events
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|event| match event {
+            MigrationEvent::PagesDiscovered { count } => ("discovered", count.to_string()),
+            MigrationEvent::PageStarted { title, .. } => ("started", title.clone()),
+            MigrationEvent::PageWritten { title, .. } => ("written", title.clone()),
+            MigrationEvent::PageFailed { title, .. } => ("failed", title.clone()),
+            MigrationEvent::RunFinished { .. } => ("finished", String::new()),
+            event => panic!("unexpected event: {:?}", event),
+        })
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            ("started", "中断".to_string()),
+            ("failed", "中断".to_string()),
+            ("discovered", "2".to_string()),
+            ("started", "失敗".to_string()),
+            ("failed", "失敗".to_string()),
+            ("started", "成功".to_string()),
+            ("written", "成功".to_string()),
+            ("finished", String::new()),
+        ]
+    );
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use notion_client::{endpoints::Client, objects::page::Page};
+use notion_to_obsidian_rs::{
+    traits::post_processor::{
+        AppendComment, PostProcessor, PostProcessorChain, SetCheckbox, WriteNoteUrl,
+    },
+    NotionToObsidianError,
+};
+use serde_json::json;
+
+use common::page;
+
+// 呼び出された順番を記録する後処理
+struct RecordingProcessor {
+    name: &'static str,
+    fail: bool,
+    calls: Arc<Mutex<Vec<&'static str>>>,
+}
+
+#[async_trait]
+impl PostProcessor for RecordingProcessor {
+    async fn process(&self, _page: &Page, _client: &Client) -> Result<(), NotionToObsidianError> {
+        self.calls.lock().unwrap().push(self.name);
+        if self.fail {
+            return Err(NotionToObsidianError::ConversionError(
+                "失敗しました".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+fn chain(
+    steps: &[(&'static str, bool)],
+    calls: &Arc<Mutex<Vec<&'static str>>>,
+) -> PostProcessorChain {
+    steps
+        .iter()
+        .fold(PostProcessorChain::new(), |chain, &(name, fail)| {
+            chain.with(Box::new(RecordingProcessor {
+                name,
+                fail,
+                calls: calls.clone(),
+            }))
+        })
+}
+
+fn write_note_url(vault_name: Option<&str>) -> WriteNoteUrl {
+    WriteNoteUrl {
+        property: "Obsidian".to_string(),
+        vault_root: PathBuf::from("/vault"),
+        vault_name: vault_name.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_note_url_is_vault_relative_path() {
+    let note = Path::new("/vault/notion/Rust メモ.md");
+
+    assert_eq!(write_note_url(None).note_url(note), "notion/Rust メモ.md");
+}
+
+#[test]
+fn test_note_url_is_obsidian_uri_with_vault_name() {
+    let note = Path::new("/vault/notion/Rust メモ.md");
+
+    assert_eq!(
+        write_note_url(Some("My Vault")).note_url(note),
+        "obsidian://open?vault=My%20Vault&file=notion%2FRust%20%E3%83%A1%E3%83%A2"
+    );
+}
+
+#[tokio::test]
+async fn test_retry_skips_completed_steps() {
+    let client = Client::new(String::new(), None).expect("failed to create client");
+    let page = page("1aeb266e-0c70-8060-a6fe-c6eb458e1379", json!({}));
+    let note = Path::new("/vault/メモ.md");
+    let calls = Arc::new(Mutex::new(Vec::new()));
+
+    let failing = chain(
+        &[("checkbox", false), ("comment", false), ("archive", true)],
+        &calls,
+    );
+    let mut completed = Vec::new();
+    let result = failing
+        .process_steps(&page, &client, note, &mut completed)
+        .await;
+    assert!(result.is_err());
+    assert_eq!(completed, vec!["checkbox", "comment"]);
+
+    // 再試行では完了していない処理だけを実行する（処理の追加・並べ替えがあっても）
+    calls.lock().unwrap().clear();
+    let retry = chain(
+        &[
+            ("status", false),
+            ("comment", false),
+            ("archive", false),
+            ("checkbox", false),
+        ],
+        &calls,
+    );
+    retry
+        .process_steps(&page, &client, note, &mut completed)
+        .await
+        .expect("failed to retry");
+    assert_eq!(*calls.lock().unwrap(), vec!["status", "archive"]);
+    assert_eq!(completed, vec!["checkbox", "comment", "status", "archive"]);
+}
+
+#[test]
+fn test_step_ids_include_settings() {
+    let checkbox = SetCheckbox {
+        property: "移行済み".to_string(),
+        checked: true,
+    };
+    assert_eq!(checkbox.step_id(), "set_checkbox:移行済み=true");
+
+    let comment = |text: &str| AppendComment {
+        text: text.to_string(),
+    };
+    assert_ne!(
+        comment("移行済み").step_id(),
+        comment("移行しました").step_id()
+    );
+}
+
+#[tokio::test]
+async fn test_chain_runs_in_order_and_stops_at_first_error() {
+    let client = Client::new(String::new(), None).expect("failed to create client");
+    let page = page("1aeb266e-0c70-8060-a6fe-c6eb458e1379", json!({}));
+    let calls = Arc::new(Mutex::new(Vec::new()));
+
+    let succeeding = chain(
+        &[("checkbox", false), ("status", false), ("comment", false)],
+        &calls,
+    );
+    succeeding
+        .process(&page, &client)
+        .await
+        .expect("failed to process");
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec!["checkbox", "status", "comment"]
+    );
+
+    calls.lock().unwrap().clear();
+    let failing = chain(
+        &[("checkbox", false), ("status", true), ("comment", false)],
+        &calls,
+    );
+    let error = failing
+        .process_note(&page, &client, Path::new("/vault/メモ.md"))
+        .await
+        .expect_err("chain should fail");
+    assert_eq!(*calls.lock().unwrap(), vec!["checkbox", "status"]);
+    // 失敗した処理の名前を含める
+    assert!(matches!(
+        &error,
+        NotionToObsidianError::PostProcessError { step, .. } if step == "status"
+    ));
+}
+
+#[tokio::test]
+async fn test_note_url_without_note_is_skipped() {
+    let client = Client::new(String::new(), None).expect("failed to create client");
+    let page = page("1aeb266e-0c70-8060-a6fe-c6eb458e1379", json!({}));
+
+    assert!(write_note_url(None).process(&page, &client).await.is_ok());
+}
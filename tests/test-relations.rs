@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use chrono::Duration;
+use notion_to_obsidian_rs::relations::{relation_items, PageTitleCache, RelationResolver};
+use serde_json::json;
+
+#[test]
+fn test_title_cache_is_persisted_between_runs() {
+    let path = std::env::temp_dir()
+        .join("notion-to-obsidian-test")
+        .join("relations.json");
+    let _ = std::fs::remove_file(&path);
+
+    let cache = PageTitleCache::new();
+    cache.insert("1aeb266e-0c70-8060-a6fe-c6eb458e1379", "Rust".to_string());
+    cache.save_file(&path).expect("failed to save cache");
+
+    let loaded = PageTitleCache::new();
+    loaded
+        .load_file(&path, Duration::days(7))
+        .expect("failed to load cache");
+    assert_eq!(
+        loaded.get("1aeb266e0c708060a6fec6eb458e1379").as_deref(),
+        Some("Rust")
+    );
+}
+
+#[test]
+fn test_expired_and_legacy_titles_are_not_loaded() {
+    let path = std::env::temp_dir()
+        .join("notion-to-obsidian-test")
+        .join("relations-expired.json");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(
+        &path,
+        json!({
+            "1aeb266e0c708060a6fec6eb458e1379": { "title": "Rust", "fetched_at": "2020-01-01T00:00:00Z" },
+            "2beb266e0c708060a6fec6eb458e1379": "Memo",
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let loaded = PageTitleCache::new();
+    loaded
+        .load_file(&path, Duration::days(7))
+        .expect("failed to load cache");
+    assert_eq!(loaded.get("1aeb266e0c708060a6fec6eb458e1379"), None);
+    assert_eq!(loaded.get("2beb266e0c708060a6fec6eb458e1379"), None);
+}
+
+#[test]
+fn test_relation_items_follow_cursor() {
+    let response = json!({
+        "object": "list",
+        "results": [
+            { "object": "property_item", "type": "relation", "relation": { "id": "page-26" } },
+            { "object": "property_item", "type": "relation", "relation": { "id": "page-27" } },
+        ],
+        "next_cursor": "cursor-2",
+        "has_more": true,
+    });
+    assert_eq!(
+        relation_items(&response),
+        (
+            vec!["page-26".to_string(), "page-27".to_string()],
+            Some("cursor-2".to_string())
+        )
+    );
+
+    let last = json!({ "results": [], "next_cursor": null, "has_more": false });
+    assert_eq!(relation_items(&last), (Vec::new(), None));
+}
+
+#[test]
+fn test_relation_key_defaults_to_property_name() {
+    let resolver =
+        RelationResolver::new(Arc::new(PageTitleCache::new())).with_key("タイプ", "types");
+
+    assert_eq!(resolver.key("タイプ"), "types");
+    assert_eq!(resolver.key("関連"), "関連");
+}
@@ -0,0 +1,395 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+mod common;
+
+use common::{
+    block, date_mention, mention, paragraph, rich_text, text_block, user_mention, BLOCK_ID,
+};
+use notion_to_obsidian_rs::{
+    blocks::{self, BlockNode},
+    columns::{ColumnLayout, ColumnListRenderer},
+    dates::DateStyle,
+    renderer::{MarkdownRenderer, RenderOptions},
+    traits::{BlockRenderer, RenderContext},
+    users::UserDirectory,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn test_nested_numbered_list_is_indented_to_marker_width() {
+    let tree = vec![
+        text_block("numbered_list_item", "番号1", vec![]),
+        text_block(
+            "numbered_list_item",
+            "番号2",
+            vec![
+                text_block("numbered_list_item", "子a", vec![]),
+                text_block("numbered_list_item", "子b", vec![]),
+            ],
+        ),
+    ];
+
+    let markdown = MarkdownRenderer::new().render(&tree);
+
+    assert_eq!(markdown, "1. 番号1\n2. 番号2\n   1. 子a\n   2. 子b");
+}
+
+#[test]
+fn test_blocks_are_separated_by_blank_lines() {
+    let tree = vec![
+        text_block("paragraph", "", vec![]),
+        text_block("heading_1", "見出し１", vec![]),
+        text_block("heading_2", "見出し2", vec![]),
+        text_block("paragraph", "", vec![]),
+        text_block("bulleted_list_item", "a", vec![]),
+        text_block("bulleted_list_item", "b", vec![]),
+    ];
+
+    let markdown = MarkdownRenderer::new().render(&tree);
+
+    assert_eq!(markdown, "# 見出し１\n\n## 見出し2\n\n- a\n- b");
+}
+
+struct UpperCaseRenderer;
+
+impl BlockRenderer for UpperCaseRenderer {
+    fn render(&self, node: &BlockNode, _context: &RenderContext) -> String {
+        blocks::rich_text(&node.block)
+            .map(blocks::plain_text)
+            .unwrap_or_default()
+            .to_uppercase()
+    }
+}
+
+#[test]
+fn test_block_renderer_override_keeps_other_defaults() {
+    let tree = vec![
+        text_block("paragraph", "hello", vec![]),
+        text_block("bulleted_list_item", "item", vec![]),
+    ];
+
+    let mut renderer = MarkdownRenderer::new();
+    renderer.register("paragraph", Box::new(UpperCaseRenderer));
+
+    assert_eq!(renderer.render(&tree), "HELLO\n\n- item");
+}
+
+fn column_list() -> BlockNode {
+    block(
+        "column_list",
+        json!({}),
+        vec![
+            block(
+                "column",
+                json!({}),
+                vec![
+                    text_block("paragraph", "左1", vec![]),
+                    text_block("paragraph", "左2", vec![]),
+                ],
+            ),
+            block(
+                "column",
+                json!({}),
+                vec![text_block("paragraph", "右", vec![])],
+            ),
+        ],
+    )
+}
+
+#[test]
+fn test_column_list_sequential_layout() {
+    let mut renderer = MarkdownRenderer::new();
+    renderer.register(
+        "column_list",
+        Box::new(ColumnListRenderer::new(ColumnLayout::Sequential)),
+    );
+
+    assert_eq!(renderer.render(&[column_list()]), "左1\n\n左2\n\n---\n\n右");
+}
+
+#[test]
+fn test_column_list_callouts_layout() {
+    let mut renderer = MarkdownRenderer::new();
+    renderer.register(
+        "column_list",
+        Box::new(ColumnListRenderer::new(ColumnLayout::Callouts)),
+    );
+
+    assert_eq!(
+        renderer.render(&[column_list()]),
+        "> [!multi-column]\n>\n>> [!note]\n>> 左1\n>> \n>> 左2\n>\n>> [!note]\n>> 右"
+    );
+}
+
+fn table(has_column_header: bool, has_row_header: bool, rows: &[[&str; 2]]) -> BlockNode {
+    let rows = rows
+        .iter()
+        .map(|row| {
+            let cells: Vec<Value> = row.iter().map(|cell| json!([rich_text(cell)])).collect();
+            block("table_row", json!({ "cells": cells }), vec![])
+        })
+        .collect();
+    block(
+        "table",
+        json!({
+            "table_width": 2,
+            "has_column_header": has_column_header,
+            "has_row_header": has_row_header
+        }),
+        rows,
+    )
+}
+
+#[test]
+fn test_table_escapes_pipes_and_newlines() {
+    let tree = vec![table(
+        true,
+        true,
+        &[["名前", "値"], ["a|b", "1行目\n2行目"]],
+    )];
+
+    assert_eq!(
+        MarkdownRenderer::new().render(&tree),
+        "| **名前** | 値 |\n| --- | --- |\n| **a\\|b** | 1行目<br>2行目 |"
+    );
+}
+
+#[test]
+fn test_table_without_column_header_gets_empty_header() {
+    let mut tree = vec![table(false, false, &[["a", "b"], ["c", "d"]])];
+    // 見出しのない表でもメンションはリンクにする
+    let page = mention(
+        json!({ "type": "page", "page": { "id": BLOCK_ID } }),
+        "設計メモ",
+    );
+    tree[0].children[1] = block(
+        "table_row",
+        json!({ "cells": [[page], [rich_text("d")]] }),
+        vec![],
+    );
+
+    assert_eq!(
+        MarkdownRenderer::new().render(&tree),
+        "| | |\n| --- | --- |\n| a | b |\n| [[設計メモ]] | d |"
+    );
+}
+
+const SOURCE_ID: &str = "1b4b266e-0c70-80d6-bba2-e7a6a1e0c2f4";
+
+fn synced_block(synced_from: Option<&str>) -> BlockNode {
+    let synced_from = synced_from.map(|id| json!({ "type": "block_id", "block_id": id }));
+    block(
+        "synced_block",
+        json!({ "synced_from": synced_from }),
+        vec![
+            paragraph("2aeb266e-0c70-8060-a6fe-c6eb458e1379", "同期1"),
+            paragraph("3aeb266e-0c70-8060-a6fe-c6eb458e1379", "同期2"),
+        ],
+    )
+}
+
+#[test]
+fn test_synced_block_is_expanded_by_default() {
+    let tree = vec![synced_block(None)];
+
+    assert_eq!(MarkdownRenderer::new().render(&tree), "同期1\n\n同期2");
+}
+
+#[test]
+fn test_synced_block_is_replaced_with_embed() {
+    // リストの中の同期ブロックもインデントしたまま置き換える
+    let tree = vec![text_block(
+        "bulleted_list_item",
+        "項目",
+        vec![synced_block(Some(SOURCE_ID))],
+    )];
+    let options = RenderOptions {
+        synced_embeds: HashMap::from([(
+            BLOCK_ID.replace('-', ""),
+            "![[設計メモ#^1b4b266e0c7080d6bba2e7a6a1e0c2f4]]".to_string(),
+        )]),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        MarkdownRenderer::new().render_with(&tree, &options),
+        "- 項目\n  ![[設計メモ#^1b4b266e0c7080d6bba2e7a6a1e0c2f4]]"
+    );
+}
+
+#[test]
+fn test_original_synced_block_anchor_covers_all_blocks() {
+    let tree = vec![synced_block(None)];
+    let options = RenderOptions {
+        anchors: HashSet::from([BLOCK_ID.replace('-', "")]),
+        ..Default::default()
+    };
+
+    // 埋め込みで同期ブロックの内容がすべて表示されるよう、1つのブロックにまとめてアンカーを付ける
+    assert_eq!(
+        MarkdownRenderer::new().render_with(&tree, &options),
+        "> [!note]\n> 同期1\n> \n> 同期2\n\n^1aeb266e0c708060a6fec6eb458e1379"
+    );
+}
+
+#[test]
+fn test_footnote_is_attached_to_commented_block() {
+    // 同じ本文のブロックがあっても、コメントが付いたブロックにのみ脚注を付ける
+    let tree = vec![
+        common::paragraph("1b4b266e-0c70-80d6-bba2-e7a6a1e0c2f4", "同じ本文"),
+        text_block("bulleted_list_item", "同じ本文", vec![]),
+    ];
+    let options = RenderOptions {
+        footnotes: HashMap::from([(BLOCK_ID.replace('-', ""), "c1".to_string())]),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        MarkdownRenderer::new().render_with(&tree, &options),
+        "同じ本文\n\n- 同じ本文 [^c1]"
+    );
+}
+
+#[test]
+fn test_user_mentions_are_linked_per_span() {
+    // 「@Al」が「@Alice」の一部として置き換えられたり、コード内が書き換えられたりしない
+    let tree = vec![
+        block(
+            "paragraph",
+            json!({
+                "rich_text": [
+                    user_mention("1ceb266e-0c70-8060-a6fe-c6eb458e1379", "Alice"),
+                    rich_text(" と "),
+                    user_mention("1deb266e-0c70-8060-a6fe-c6eb458e1379", "Al"),
+                ],
+                "color": "default"
+            }),
+            vec![],
+        ),
+        block(
+            "code",
+            json!({
+                "rich_text": [rich_text("@Alice")],
+                "caption": [],
+                "language": "plain text"
+            }),
+            vec![],
+        ),
+    ];
+    let options = RenderOptions {
+        user_directory: Some(Arc::new(UserDirectory::default())),
+        ..Default::default()
+    };
+
+    let renderer = MarkdownRenderer::new();
+    assert_eq!(
+        renderer.render_with(&tree, &options),
+        "[[Alice]] と [[Al]]\n\n```\n@Alice\n```"
+    );
+    assert_eq!(renderer.render(&tree), "@Alice と @Al\n\n```\n@Alice\n```");
+}
+
+#[test]
+fn test_date_mentions_are_rendered_per_span() {
+    // 本文に同じ表記の文字列があってもメンションのみを置き換え、二重にリンクにしない
+    let tree = vec![
+        block(
+            "paragraph",
+            json!({
+                "rich_text": [
+                    rich_text("締切は "),
+                    date_mention("2025-03-06T10:00:00.000+09:00", "2025-03-06 10:00"),
+                    rich_text("（2025-03-06 10:00 に決定）"),
+                ],
+                "color": "default"
+            }),
+            vec![],
+        ),
+        block(
+            "paragraph",
+            json!({
+                "rich_text": [date_mention("2025-03-06T10:00:00.000+09:00", "2025-03-06 10:00")],
+                "color": "default"
+            }),
+            vec![],
+        ),
+    ];
+    let options = RenderOptions {
+        date_style: Some(DateStyle::daily_note()),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        MarkdownRenderer::new().render_with(&tree, &options),
+        "締切は [[2025-03-06]] 10:00（2025-03-06 10:00 に決定）\n\n[[2025-03-06]] 10:00"
+    );
+}
+
+#[test]
+fn test_date_mentions_keep_display_text_without_style() {
+    let tree = vec![block(
+        "paragraph",
+        json!({
+            "rich_text": [date_mention("2025-03-06T10:00:00.000+09:00", "March 6, 2025 10:00 AM")],
+            "color": "default"
+        }),
+        vec![],
+    )];
+
+    assert_eq!(
+        MarkdownRenderer::new().render(&tree),
+        "March 6, 2025 10:00 AM"
+    );
+}
+
+fn to_do(checked: bool, rich_text: Vec<Value>) -> BlockNode {
+    block(
+        "to_do",
+        json!({ "rich_text": rich_text, "checked": checked, "color": "default" }),
+        vec![],
+    )
+}
+
+#[test]
+fn test_tasks_mode_moves_date_mentions_to_due_date() {
+    let tree = vec![
+        to_do(
+            false,
+            vec![
+                rich_text("資料を送る "),
+                date_mention("2025-03-06T10:00:00.000+09:00", "2025-03-06 10:00"),
+            ],
+        ),
+        // 完了日はNotionにないため付けない
+        to_do(true, vec![rich_text("資料を送る")]),
+    ];
+    let options = RenderOptions {
+        tasks_mode: true,
+        footnotes: HashMap::from([(BLOCK_ID.replace('-', ""), "c1".to_string())]),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        MarkdownRenderer::new().render_with(&tree, &options),
+        "- [ ] 資料を送る [^c1] 📅 2025-03-06\n- [x] 資料を送る [^c1]"
+    );
+}
+
+#[test]
+fn test_to_do_keeps_date_mentions_without_tasks_mode() {
+    let tree = vec![to_do(
+        false,
+        vec![
+            rich_text("資料を送る "),
+            date_mention("2025-03-06T10:00:00.000+09:00", "2025-03-06 10:00"),
+        ],
+    )];
+
+    assert_eq!(
+        MarkdownRenderer::new().render(&tree),
+        "- [ ] 資料を送る 2025-03-06 10:00"
+    );
+}
@@ -0,0 +1,32 @@
+use notion_to_obsidian_rs::vault::notion_id;
+
+#[test]
+fn test_notion_id_is_read_from_frontmatter() {
+    let content =
+        "---\ntitle: \"メモ\"\nnotion_id: 1aeb266e-0c70-8060-a6fe-c6eb458e1379\n---\n本文\n";
+
+    assert_eq!(
+        notion_id(content).as_deref(),
+        Some("1aeb266e0c708060a6fec6eb458e1379")
+    );
+}
+
+#[test]
+fn test_notion_id_is_read_from_crlf_frontmatter() {
+    let content =
+        "---\r\ntitle: \"メモ\"\r\nnotion_id: 1aeb266e-0c70-8060-a6fe-c6eb458e1379\r\n---\r\n本文\r\n";
+
+    assert_eq!(
+        notion_id(content).as_deref(),
+        Some("1aeb266e0c708060a6fec6eb458e1379")
+    );
+}
+
+#[test]
+fn test_notion_id_outside_frontmatter_is_ignored() {
+    assert_eq!(notion_id("本文\nnotion_id: 1aeb266e\n"), None);
+    assert_eq!(
+        notion_id("---\ntitle: \"メモ\"\n---\nnotion_id: 1aeb266e\n"),
+        None
+    );
+}